@@ -1,25 +1,139 @@
 pub mod lock_free_ring_buffer {
 
-    use std::cell::UnsafeCell;
-    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::cell::{Cell, UnsafeCell};
+    use std::fmt;
+    use std::hint::spin_loop;
+    use std::io::IoSlice;
+    use std::marker::PhantomData;
+    use std::mem::MaybeUninit;
+    use std::ops::{ControlFlow, Deref, DerefMut};
+    use std::sync::atomic::{fence, AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::{Duration, Instant};
 
     const fn is_power_of_two(n: usize) -> bool {
         n > 0 && (n & (n - 1)) == 0
     }
 
+    // Power-of-two slot count for a requested size, i.e. the SIZE to pick, so
+    // related structures can be sized to match. Never less than 2, the smallest
+    // SIZE with a usable slot; remember one slot is always kept free, so the
+    // usable capacity is the result minus one. Panics when the next power of two
+    // does not fit in a usize, in release builds too; usize::next_power_of_two
+    // only panics in debug builds and wraps to 0 otherwise.
+    pub const fn round_capacity(requested: usize) -> usize {
+        match requested.checked_next_power_of_two() {
+            Some(slots) if slots < 2 => 2,
+            Some(slots) => slots,
+            None => panic!("round_capacity overflows usize"),
+        }
+    }
+
+    // Moves a cursor n positions forward. RingBuffer cursors are free-running
+    // counters, so the add wraps at u32::MAX rather than overflowing.
+    const fn advance_cursor(curs: u32, n: u32) -> u32 {
+        curs.wrapping_add(n)
+    }
+
+    // Opt-in compile-time guard against accidentally huge inline buffers: fails the
+    // build when SIZE slots of T exceed a byte budget, e.g.
+    //
+    //     assert_ring_fits!(Frame, 4096, 64 * 1024);
+    //     let rb: RingBuffer<Frame, 4096> = RingBuffer::new(); // on the stack
+    #[macro_export]
+    macro_rules! assert_ring_fits {
+        ($t:ty, $size:expr, $max_bytes:expr) => {
+            const _: () = assert!(
+                ::core::mem::size_of::<$t>() * $size <= $max_bytes,
+                "ring buffer storage (SIZE * size_of::<T>()) exceeds the byte budget"
+            );
+        };
+    }
+
+    // Backoff for the blocking operations: spin first, then yield, then park briefly
+    fn backoff(step: &mut u32) {
+        if *step < 6 {
+            for _ in 0..(1 << *step) {
+                spin_loop();
+            }
+        } else if *step < 10 {
+            thread::yield_now();
+        } else {
+            thread::park_timeout(Duration::from_micros(100));
+        }
+        *step = (*step + 1).min(10);
+    }
+
+    // The cursors are free-running positions that wrap at u32::MAX; the slot for a
+    // position is position & BUFFER_MASK. SIZE divides 2^32, so the slot sequence
+    // stays continuous across the wrap, and positions tell laps apart, which the
+    // per-slot sequence stamps rely on. A slot free for the write at position p is
+    // stamped p, publishing the item stamps it p + 1, and the reader that takes
+    // the item stamps it p + SIZE, freeing it for the next lap. Producers only
+    // claim slots stamped with their own position, so a slot is never rewritten
+    // while a reader that already moved the read cursor past it is still taking it.
+    //
+    // Wrapping is the defined behavior, not an overflow. Positions are only ever
+    // compared through wrapping differences and stamps only for equality, so every
+    // check gives the same answer on both sides of u32::MAX as long as the values
+    // compared are less than 2^31 positions apart: the cursors never drift more
+    // than SIZE apart, and a position kept by a caller stays meaningful for the
+    // next 2^31 operations. There is deliberately no saturating mode. A u32
+    // position runs out after 2^32 operations, which a busy buffer reaches within
+    // minutes, and refusing from then on would only make the buffer unusable.
+    //
+    // repr(C) keeps the layout fixed so a buffer can live in shared memory, see from_raw_parts
+    #[repr(C)]
     pub struct RingBuffer<T, const SIZE: usize> {
-        buffer: [UnsafeCell<T>; SIZE], // Buffer storage
-        write_cursor: AtomicU32,       // Write cursor
-        read_cursor: AtomicU32,        // Read cursor
+        buffer: [UnsafeCell<T>; SIZE],       // Buffer storage
+        slot_seq: [AtomicU32; SIZE],         // Per-slot stamp: free, published or taken, see above
+        write_cursor: AtomicU32,             // Write cursor, published to consumers
+        write_claim: AtomicU32,              // Next slot claimed by producers
+        read_cursor: AtomicU32,              // Read cursor
+        epoch: AtomicU32,                    // Bumped by every clear_concurrent
+        waker_enabled: bool,                 // Built with with_waker, so reads wake poll_write
+        write_waker: Mutex<Option<Waker>>,   // Writer parked in poll_write
+        write_waker_registered: AtomicBool,  // Lets reads skip the lock when nobody waits
+        poisoned: AtomicBool,                // A producer panicked inside a reservation
+        poisoned_at: AtomicU32,              // Start of the earliest poisoned frame, once poisoned
+        label: Option<&'static str>,         // Name shown in Debug output, see named
+        wrap_count: AtomicU64,               // Times the write cursor wrapped past index 0
+        profiling: AtomicBool,               // Sample occupancy on every write, see set_profiling
+        occupancy_histogram: [AtomicU64; 4], // Writes bucketed by occupancy quarter
     }
 
     // SAFETY: Thread-safe when T is Send because:
-    // - Atomic cursors handle concurrent access
-    // - Power-of-two size prevents slot contention
+    // - Atomic cursors hand each position to one producer and one consumer
+    // - The slot stamps keep a producer out of a slot until its last reader is done
     // - UnsafeCell provides safe interior mutability
     unsafe impl<T: Send, const SIZE: usize> Sync for RingBuffer<T, SIZE> {}
 
-    // Implement Default for RingBuffer
+    // Reports the label and occupancy; the buffered items are not printed, so T
+    // need not be Debug
+    impl<T: Default, const SIZE: usize> fmt::Debug for RingBuffer<T, SIZE> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let (read_curs, write_curs, len): (u32, u32, usize) = self.occupancy_snapshot();
+            f.debug_struct("RingBuffer")
+                .field("label", &self.label)
+                .field("read_cursor", &read_curs)
+                .field("write_cursor", &write_curs)
+                .field("len", &len)
+                .field("capacity", &Self::CAPACITY)
+                .finish()
+        }
+    }
+
+    impl<T, const SIZE: usize> Default for RingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl<T, const SIZE: usize> RingBuffer<T, SIZE>
     where
         T: Default,
@@ -27,47 +141,625 @@ pub mod lock_free_ring_buffer {
     {
         const BUFFER_MASK: u32 = (SIZE - 1) as u32;
 
+        // Usable capacity, one slot is always kept free to tell full from empty
+        pub const CAPACITY: usize = SIZE - 1;
+
+        // Occupied slots between the two cursors, as the masked difference. Full is
+        // used_slots == BUFFER_MASK and empty is 0, the same boundaries as comparing
+        // the advanced write cursor with the read cursor. The callers still branch
+        // on the result; this only gives every occupancy check one definition.
+        const fn used_slots(write_curs: u32, read_curs: u32) -> u32 {
+            write_curs.wrapping_sub(read_curs) & Self::BUFFER_MASK
+        }
+
+        const fn slot_index(curs: u32) -> usize {
+            (curs & Self::BUFFER_MASK) as usize
+        }
+
         pub fn new() -> Self {
             const {
                 assert!(is_power_of_two(SIZE), "Size must be a power of two");
             }
+            Self::new_unchecked()
+        }
+
+        // Same as new, with a label that the Debug output includes so buffers can be
+        // told apart in logs
+        pub fn named(label: &'static str) -> Self {
+            let mut buffer: Self = Self::new();
+            buffer.label = Some(label);
+            buffer
+        }
+
+        // Same as new, for a buffer written through poll_write. Every read that frees
+        // a slot then pays a fence to check for a parked writer; buffers built
+        // with new skip it.
+        pub fn with_waker() -> Self {
+            let mut buffer: Self = Self::new();
+            buffer.waker_enabled = true;
+            buffer
+        }
+
+        pub fn label(&self) -> Option<&'static str> {
+            self.label
+        }
+
+        // Const constructor that seeds the first N slots with items, so a pre-filled
+        // buffer can be a static. Slots past N are filled with copies of items[0]
+        // because T::default() is not callable in const context. Requires
+        // 0 < N < SIZE, checked at compile time: with N == 0 there is nothing to
+        // fill the slots with, so from_array([]) does not compile; use new for an
+        // empty buffer.
+        pub const fn from_array<const N: usize>(items: [T; N]) -> Self
+        where
+            T: Copy,
+        {
+            const {
+                assert!(is_power_of_two(SIZE), "Size must be a power of two");
+                assert!(N > 0 && N < SIZE, "from_array needs 0 < N < SIZE");
+            }
+
+            let mut slots: [MaybeUninit<UnsafeCell<T>>; SIZE] = [const { MaybeUninit::uninit() }; SIZE];
+            let mut slot_seq: [AtomicU32; SIZE] = [const { AtomicU32::new(0) }; SIZE];
+            let mut index: usize = 0;
+            while index < SIZE {
+                let item: T = if index < N { items[index] } else { items[0] };
+                slots[index] = MaybeUninit::new(UnsafeCell::new(item));
+                // Seeded slots are published, the rest free for their first write
+                slot_seq[index] = AtomicU32::new(if index < N { index as u32 + 1 } else { index as u32 });
+                index += 1;
+            }
+
+            RingBuffer {
+                // SAFETY: every slot was initialized above, and MaybeUninit<U> has the
+                // same layout as U
+                buffer: unsafe { std::ptr::read(slots.as_ptr() as *const [UnsafeCell<T>; SIZE]) },
+                slot_seq,
+                write_cursor: AtomicU32::new(N as u32),
+                write_claim: AtomicU32::new(N as u32),
+                read_cursor: AtomicU32::new(0),
+                epoch: AtomicU32::new(0),
+                waker_enabled: false,
+                write_waker: Mutex::new(None),
+                write_waker_registered: AtomicBool::new(false),
+                poisoned: AtomicBool::new(false),
+                poisoned_at: AtomicU32::new(0),
+                label: None,
+                wrap_count: AtomicU64::new(0),
+                profiling: AtomicBool::new(false),
+                occupancy_histogram: [const { AtomicU64::new(0) }; 4],
+            }
+        }
+
+        // Runtime-checked constructor for generic wrappers: returns None instead
+        // of failing const evaluation when SIZE is not a power of two
+        pub fn try_new() -> Option<Self> {
+            if !is_power_of_two(SIZE) {
+                return None;
+            }
+            Some(Self::new_unchecked())
+        }
+
+        /// Attaches a view to a buffer that lives in externally provided storage, such
+        /// as a /dev/shm mapping shared between processes for inter-process SPSC. The
+        /// region is set up once, e.g. with `ptr::write(region, RingBuffer::new())`,
+        /// and every process then attaches its own view.
+        ///
+        /// # Safety
+        ///
+        /// - `storage` must be non-null, aligned for `RingBuffer<T, SIZE>` and point to
+        ///   a buffer initialized with `new` (or written bytewise by a program built
+        ///   from this same source with the same `T` and `SIZE`, so layouts match).
+        /// - The storage must stay mapped at that address and must not be moved,
+        ///   reinitialized or dropped while any view of lifetime `'a` is alive. The
+        ///   views never drop the buffer; whoever created it is responsible for that.
+        /// - The memory must support atomic operations from every attached party;
+        ///   ordinary shared mappings do.
+        /// - `T` must be meaningful in every process: plain data with no pointers,
+        ///   references, heap ownership or process-local handles.
+        /// - The usual single-producer/single-consumer rules of the methods used
+        ///   apply across all views combined.
+        /// - `poll_write` keeps its waker behind a process-local lock and must not
+        ///   be used through a view shared between processes.
+        /// - A buffer shared between processes must not be `named`: the label is a
+        ///   process-local pointer, read by the `Debug` impl.
+        pub unsafe fn from_raw_parts<'a>(storage: *const Self) -> &'a Self {
+            &*storage
+        }
+
+        fn new_unchecked() -> Self {
             RingBuffer {
+                slot_seq: std::array::from_fn(|index| AtomicU32::new(index as u32)),
                 write_cursor: AtomicU32::new(0),
+                write_claim: AtomicU32::new(0),
                 read_cursor: AtomicU32::new(0),
+                epoch: AtomicU32::new(0),
+                waker_enabled: false,
+                write_waker: Mutex::new(None),
+                write_waker_registered: AtomicBool::new(false),
+                poisoned: AtomicBool::new(false),
+                poisoned_at: AtomicU32::new(0),
+                label: None,
+                wrap_count: AtomicU64::new(0),
+                profiling: AtomicBool::new(false),
+                occupancy_histogram: std::array::from_fn(|_| AtomicU64::new(0)),
                 buffer: std::array::from_fn(|_| UnsafeCell::new(T::default())),
             }
         }
 
         pub fn try_write(&self, item: T) -> bool {
-            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Relaxed);
+            self.try_push(item).is_ok()
+        }
+
+        // try_write that reports the free-running position the item was written
+        // at, for use with is_consumed. The slot index is pos & (SIZE - 1).
+        pub fn try_write_at(&self, item: T) -> Option<u32> {
+            self.try_push(item).ok()
+        }
+
+        // Whether the item written at pos (from try_write_at) has been read, i.e.
+        // the read cursor has moved past it. Positions are compared by signed
+        // wrapping difference, so the answer holds for 2^31 writes after pos
+        // rather than only until its slot is reused.
+        pub fn is_consumed(&self, pos: u32) -> bool {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+            (pos.wrapping_sub(curr_read_curs) as i32) < 0
+        }
+
+        // try_write that hands the item back when the buffer is full, and returns
+        // the position it was written at otherwise
+        fn try_push(&self, item: T) -> Result<u32, T> {
+            if self.poisoned.load(Ordering::Relaxed) {
+                return Err(item); // Nothing after a poisoned frame is ever published
+            }
 
             loop {
+                // Producers race on write_claim; consumers only follow write_cursor,
+                // which is published once the slot has been written
+                let curr_write_curs: u32 = self.write_claim.load(Ordering::Relaxed);
                 let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
-                let next_write_curs: u32 = (curr_write_curs + 1) & Self::BUFFER_MASK;
+                let next_write_curs: u32 = advance_cursor(curr_write_curs, 1);
 
                 // Check if the buffer is full
-                if next_write_curs == curr_read_curs {
-                    return false; // Buffer is full
+                if Self::used_slots(curr_write_curs, curr_read_curs) == Self::BUFFER_MASK {
+                    return Err(item); // Buffer is full
+                }
+                match self.claim_offset(curr_write_curs, 1) {
+                    0 => {}
+                    offset if offset > 0 => continue, // Already claimed by another producer
+                    _ => return Err(item),            // Its last reader is still taking it
                 }
 
                 // Attempt to write the item
                 if self
-                    .write_cursor
+                    .write_claim
                     .compare_exchange_weak(curr_write_curs, next_write_curs, Ordering::AcqRel, Ordering::Relaxed)
                     .is_ok()
                 {
                     unsafe {
-                        *self.buffer[curr_write_curs as usize].get() = item; // Write the item
+                        self.write_slot(curr_write_curs, item);
                     }
-                    return true; // Write successful
+                    self.publish(curr_write_curs, 1);
+                    if self.is_behind_poisoned_frame(curr_write_curs) {
+                        // Poisoned while we wrote: no consumer will ever reach the slot,
+                        // so the item can be taken back
+                        return Err(unsafe { self.take_slot(curr_write_curs) });
+                    }
+                    return Ok(curr_write_curs); // Write successful
                 }
             }
         }
 
-        pub fn try_read(&self) -> Option<T> {
-            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+        // Claims n contiguous slots with a single CAS so a multi-item frame is never
+        // interleaved with another producer's items. The frame becomes visible to
+        // consumers when the reservation is committed or dropped. Writes claimed
+        // after it still go through without waiting, but consumers see items in
+        // claim order, so those writes stay invisible until the frame is published.
+        // A leaked guard (mem::forget) is never published: consumers stop at the
+        // frame for good, while writes keep succeeding until the buffer fills.
+        pub fn try_reserve_n(&self, n: usize) -> Option<WriteReservation<'_, T, SIZE>> {
+            if n == 0 || n > Self::CAPACITY || self.poisoned.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            loop {
+                let curr_write_curs: u32 = self.write_claim.load(Ordering::Relaxed);
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+                let used: u32 = Self::used_slots(curr_write_curs, curr_read_curs);
+
+                if used + n as u32 > Self::BUFFER_MASK {
+                    return None; // Not enough free slots
+                }
+                match self.claim_offset(curr_write_curs, n as u32) {
+                    0 => {}
+                    offset if offset > 0 => continue, // Already claimed by another producer
+                    _ => return None,                 // A last reader is still taking one
+                }
+
+                let next_write_curs: u32 = advance_cursor(curr_write_curs, n as u32);
+                if self
+                    .write_claim
+                    .compare_exchange_weak(curr_write_curs, next_write_curs, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    return Some(WriteReservation {
+                        ring: self,
+                        start: curr_write_curs,
+                        len: n as u32,
+                    });
+                }
+            }
+        }
+
+        // Clock-free bounded write: one attempt plus up to spins retries with a
+        // spin_loop hint between them, then the item is handed back. No Duration,
+        // parking or syscalls, so the worst case is a fixed number of iterations.
+        pub fn try_write_spins(&self, item: T, spins: u32) -> Result<(), T> {
+            let mut item: T = item;
+            for _ in 0..spins {
+                match self.try_push(item) {
+                    Ok(_) => return Ok(()),
+                    Err(rejected) => item = rejected,
+                }
+                spin_loop();
+            }
+            self.try_push(item).map(|_| ())
+        }
+
+        // Best-effort write: waits up to park_budget for space and, if the buffer is
+        // still full by then, drops the item and returns false
+        pub fn write_or_drop(&self, item: T, park_budget: Duration) -> bool {
+            let deadline: Instant = Instant::now() + park_budget;
+            let mut item: T = item;
+            let mut step: u32 = 0;
+            loop {
+                match self.try_push(item) {
+                    Ok(_) => return true,
+                    Err(rejected) => item = rejected,
+                }
+                if Instant::now() >= deadline {
+                    return false;
+                }
+                backoff(&mut step);
+            }
+        }
+
+        // Blocks until all of src is written. Each round claims as many slots as are
+        // free and publishes them as one chunk, so a slice longer than the capacity
+        // goes in over several rounds as the consumer drains. Backs off between
+        // rounds, parking once the buffer has stayed full for a while. Panics if the
+        // buffer is poisoned, since the remaining items could never be published.
+        pub fn write_slice_blocking(&self, src: &[T])
+        where
+            T: Copy,
+        {
+            let mut remaining: &[T] = src;
+            let mut step: u32 = 0;
+            while !remaining.is_empty() {
+                assert!(!self.is_poisoned(), "write_slice_blocking on a poisoned buffer");
+
+                let curr_write_curs: u32 = self.write_claim.load(Ordering::Relaxed);
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+                let used: u32 = Self::used_slots(curr_write_curs, curr_read_curs);
+                let free: usize = (Self::BUFFER_MASK - used) as usize;
+
+                let Some(mut reservation) = self.try_reserve_n(free.min(remaining.len())) else {
+                    backoff(&mut step); // Full, or another producer took the space
+                    continue;
+                };
+                let (first, wrapped): (&mut [T], &mut [T]) = reservation.slices();
+                let (head, tail): (&[T], &[T]) = remaining.split_at(first.len() + wrapped.len());
+                first.copy_from_slice(&head[..first.len()]);
+                wrapped.copy_from_slice(&head[first.len()..]);
+                reservation.commit();
+
+                remaining = tail;
+                step = 0;
+            }
+        }
+
+        // Builds the item in place in a reserved slot. If f panics the reservation is
+        // dropped unpublished and the buffer is poisoned, see try_read_checked.
+        pub fn try_emplace<F: FnOnce(&mut T)>(&self, f: F) -> bool {
+            let Some(mut reservation) = self.try_reserve_n(1) else {
+                return false;
+            };
+            f(&mut reservation.slices().0[0]);
+            reservation.commit();
+            true
+        }
+
+        // Lossy batch write: pushes every item, evicting the oldest buffered items
+        // to make room, and returns how many were evicted across the batch. On a
+        // poisoned buffer no write can succeed, so it stops and drops the rest.
+        pub fn force_write_n<I: IntoIterator<Item = T>>(&self, items: I) -> usize {
+            let mut evicted: usize = 0;
+            for mut item in items {
+                while let Err(rejected) = self.try_push(item) {
+                    if self.is_poisoned() {
+                        return evicted;
+                    }
+                    item = rejected;
+                    if self.try_read().is_some() {
+                        evicted += 1;
+                    }
+                }
+            }
+            evicted
+        }
+
+        // Wait-free write for the sole producer of an Spsc split: nobody else moves
+        // the write cursors, so the slot is written and published without a CAS.
+        // The read cursor comes from the producer's cache and is only reloaded once
+        // the refresh interval has passed or the buffer looks full. A stale read
+        // cursor is always behind the real one, so it can only under-report space.
+        fn try_push_single_producer(&self, item: T, cache: &ReadCursorCache) -> Result<(), T> {
+            if self.poisoned.load(Ordering::Relaxed) {
+                return Err(item); // Poisoned before the split
+            }
+
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Relaxed);
+            let mut curr_read_curs: u32 = cache.read_curs.load(Ordering::Relaxed);
+            let writes_since_refresh: u32 = cache.writes_since_refresh.load(Ordering::Relaxed);
+            let next_write_curs: u32 = advance_cursor(curr_write_curs, 1);
+
+            if Self::used_slots(curr_write_curs, curr_read_curs) == Self::BUFFER_MASK
+                || writes_since_refresh >= cache.refresh_interval
+            {
+                curr_read_curs = self.read_cursor.load(Ordering::Acquire);
+                cache.read_curs.store(curr_read_curs, Ordering::Relaxed);
+                cache.writes_since_refresh.store(0, Ordering::Relaxed);
+                if Self::used_slots(curr_write_curs, curr_read_curs) == Self::BUFFER_MASK {
+                    return Err(item); // Buffer is full
+                }
+            }
+            if self.claim_offset(curr_write_curs, 1) != 0 {
+                return Err(item); // Its last reader is still taking it
+            }
+
+            unsafe {
+                self.write_slot(curr_write_curs, item);
+            }
+            // The stamp is kept in step with the shared paths, but write_cursor alone
+            // publishes here
+            self.slot_seq[Self::slot_index(curr_write_curs)].store(next_write_curs, Ordering::Relaxed);
+            self.write_claim.store(next_write_curs, Ordering::Relaxed);
+            self.write_cursor.store(next_write_curs, Ordering::Release);
+            self.record_write(next_write_curs);
+            cache.writes_since_refresh.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        // Poll-style write for custom executors: writes the item, taking it out of the
+        // Option, if there is space; otherwise registers the waker, leaves the item
+        // in place and returns Pending. The waker is woken by the next read that
+        // frees a slot. Only the most recently registered waker is kept. Once the
+        // buffer is poisoned no write can succeed: it returns Ready with the item
+        // still in the Option, and poisoning wakes a registered writer to see that.
+        // Panics unless the buffer was built with with_waker, since reads on other
+        // buffers never check for a parked writer.
+        pub fn poll_write(&self, cx: &mut Context<'_>, item: &mut Option<T>) -> Poll<()> {
+            assert!(
+                self.waker_enabled,
+                "poll_write needs a buffer built with RingBuffer::with_waker"
+            );
+            let Some(value) = item.take() else {
+                return Poll::Ready(());
+            };
+            let value: T = match self.try_push(value) {
+                Ok(_) => return Poll::Ready(()),
+                Err(value) => value,
+            };
+            if self.is_poisoned() {
+                *item = Some(value);
+                return Poll::Ready(());
+            }
+
+            // Register before retrying so a read in between cannot be missed
+            *self.write_waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+            self.write_waker_registered.store(true, Ordering::Relaxed);
+            fence(Ordering::SeqCst); // Pairs with the fence in wake_writer
+
+            match self.try_push(value) {
+                Ok(_) => Poll::Ready(()),
+                Err(value) => {
+                    *item = Some(value);
+                    if self.is_poisoned() {
+                        return Poll::Ready(());
+                    }
+                    Poll::Pending
+                }
+            }
+        }
+
+        // Called after a read frees space; wakes a writer parked in poll_write. Only
+        // buffers built with with_waker pay the fence.
+        fn wake_writer(&self) {
+            if !self.waker_enabled {
+                return;
+            }
+            fence(Ordering::SeqCst); // Pairs with the fence in poll_write
+            if !self.write_waker_registered.load(Ordering::Relaxed)
+                || !self.write_waker_registered.swap(false, Ordering::Relaxed)
+            {
+                return;
+            }
+
+            let waker: Option<Waker> = self.write_waker.lock().unwrap_or_else(PoisonError::into_inner).take();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+
+        // Makes n claimed and written slots visible to consumers. Each slot is
+        // stamped with its position + 1, then write_cursor is moved over every
+        // stamped slot in a row. No producer waits for the claims ahead of it: if an
+        // earlier slot is still being written, its producer moves the cursor over
+        // this one once it stamps its own. A frame pays two fences however long it
+        // is: the Release fence orders every slot write before the relaxed stamps,
+        // and the SeqCst fence orders the stamps before the cursor check.
+        fn publish(&self, start_curs: u32, n: u32) {
+            fence(Ordering::Release);
+            for offset in 0..n {
+                let curs: u32 = advance_cursor(start_curs, offset);
+                self.slot_seq[Self::slot_index(curs)].store(advance_cursor(curs, 1), Ordering::Relaxed);
+            }
+            fence(Ordering::SeqCst);
+            self.advance_write_cursor();
+        }
+
+        // SeqCst on the cursor and the stamp loads, paired with the fence in publish:
+        // a producer stamping slot p and one moving the cursor onto p each check the
+        // other's store afterwards, and SeqCst guarantees at least one of them sees
+        // it, so no stamped slot is left behind. The stamp load also acquires the
+        // slot write through publish's Release fence.
+        fn advance_write_cursor(&self) {
+            loop {
+                let curr_write_curs: u32 = self.write_cursor.load(Ordering::SeqCst);
+                let next_write_curs: u32 = advance_cursor(curr_write_curs, 1);
+                if self.slot_seq[Self::slot_index(curr_write_curs)].load(Ordering::SeqCst) != next_write_curs {
+                    return; // Not published yet, its producer moves the cursor on
+                }
+
+                if self
+                    .write_cursor
+                    .compare_exchange(curr_write_curs, next_write_curs, Ordering::SeqCst, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    self.record_write(next_write_curs);
+                }
+            }
+        }
+
+        // Signed distance between the stamps of the n slots from curs and the free
+        // stamps a claim at curs expects: 0 if all are free, negative if one still
+        // holds an item from the previous lap, positive if curs is stale and already
+        // claimed by another producer. Acquire pairs with free_slot, so the reader's
+        // take happens-before the slot is rewritten.
+        fn claim_offset(&self, curs: u32, n: u32) -> i32 {
+            for offset in 0..n {
+                let pos: u32 = advance_cursor(curs, offset);
+                let slot_seq: u32 = self.slot_seq[Self::slot_index(pos)].load(Ordering::Acquire);
+                let diff: i32 = slot_seq.wrapping_sub(pos) as i32;
+                if diff != 0 {
+                    return diff;
+                }
+            }
+            0
+        }
+
+        // Stamps the slot read at curs free for the write one lap later, once its
+        // item has been taken or left behind for good
+        fn free_slot(&self, curs: u32) {
+            self.slot_seq[Self::slot_index(curs)].store(advance_cursor(curs, SIZE as u32), Ordering::Release);
+        }
+
+        // Marks the frame at start_curs as never to be published. write_cursor stops
+        // before it for good, so the earliest poisoned frame is the one recorded.
+        fn poison(&self, start_curs: u32) {
+            // Never past an unpublished frame, so a valid origin for comparing them
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            if self.poisoned.load(Ordering::Acquire) {
+                let _ = self
+                    .poisoned_at
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |prev_curs| {
+                        (start_curs.wrapping_sub(curr_write_curs) < prev_curs.wrapping_sub(curr_write_curs))
+                            .then_some(start_curs)
+                    });
+            } else {
+                self.poisoned_at.store(start_curs, Ordering::Relaxed);
+            }
+            self.poisoned.store(true, Ordering::Release);
+            self.wake_writer(); // Lets a writer parked in poll_write see the poison
+        }
+
+        // Whether the unread item at curs sits at or behind a poisoned frame, so
+        // that no consumer will ever reach it
+        fn is_behind_poisoned_frame(&self, curs: u32) -> bool {
+            if !self.poisoned.load(Ordering::Acquire) {
+                return false;
+            }
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            let offset: u32 = curs.wrapping_sub(curr_write_curs); // Wraps huge once curs is published
+            offset <= Self::BUFFER_MASK
+                && self.poisoned_at.load(Ordering::Relaxed).wrapping_sub(curr_write_curs) <= offset
+        }
+
+        // Bookkeeping for every item that becomes visible: a wrap costs one relaxed
+        // increment per lap, the histogram only runs while profiling is on
+        fn record_write(&self, next_write_curs: u32) {
+            if next_write_curs & Self::BUFFER_MASK == 0 {
+                self.wrap_count.fetch_add(1, Ordering::Relaxed);
+            }
+
+            if self.profiling.load(Ordering::Relaxed) {
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+                let len: usize = Self::used_slots(next_write_curs, curr_read_curs) as usize;
+                self.occupancy_histogram[len * 4 / SIZE].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // Occupancy sampled as every item is published, bucketed by quarter of SIZE:
+        // bucket i counts items that left between i/4 and (i+1)/4 of the slots in use.
+        // Only writes made while profiling was on are counted.
+        pub fn occupancy_histogram(&self) -> [u64; 4] {
+            std::array::from_fn(|bucket| self.occupancy_histogram[bucket].load(Ordering::Relaxed))
+        }
+
+        // Turns occupancy sampling for occupancy_histogram on or off. Off by default,
+        // since it adds two atomic operations to every write.
+        pub fn set_profiling(&self, enabled: bool) {
+            self.profiling.store(enabled, Ordering::Relaxed);
+        }
+
+        // Number of times the write cursor wrapped past slot 0. The read cursor
+        // trails it, so this is also total throughput in units of SIZE.
+        pub fn wrap_count(&self) -> u64 {
+            self.wrap_count.load(Ordering::Relaxed)
+        }
+
+        // Splits the buffer into handles for threads that only write or only read.
+        // Producers can be cloned; once every producer is dropped the consumer
+        // sees the disconnect.
+        pub fn split(self) -> (Producer<T, SIZE>, Consumer<T, SIZE>) {
+            self.split_as()
+        }
+
+        // Like split, but the handles are restricted to one thread each, which lets
+        // the producer skip the claim CAS
+        pub fn split_spsc(self) -> (Producer<T, SIZE, Spsc>, Consumer<T, SIZE, Spsc>) {
+            self.split_as()
+        }
+
+        // Recombines the halves of a split, e.g. at shutdown to call into_vec. Fails,
+        // handing both halves back, if they come from different buffers or if other
+        // producer or consumer clones are still alive.
+        pub fn reunite<M: Mode>(
+            producer: Producer<T, SIZE, M>,
+            consumer: Consumer<T, SIZE, M>,
+        ) -> Result<Self, SplitHalves<T, SIZE, M>> {
+            if !Arc::ptr_eq(&producer.channel, &consumer.channel) || Arc::strong_count(&consumer.channel) != 2 {
+                return Err((producer, consumer));
+            }
+
+            drop(producer);
+            let channel: Channel<T, SIZE> =
+                Arc::into_inner(consumer.channel).expect("checked that no other handles exist");
+            Ok(channel.ring)
+        }
+
+        fn split_as<M: Mode>(self) -> (Producer<T, SIZE, M>, Consumer<T, SIZE, M>) {
+            let channel: Arc<Channel<T, SIZE>> = Arc::new(Channel {
+                ring: self,
+                producers: AtomicUsize::new(1),
+            });
+            (Producer::new(channel.clone()), Consumer::new(channel))
+        }
 
+        pub fn try_read(&self) -> Option<T> {
             loop {
+                // Reloaded every attempt, clear_concurrent may have moved it
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
                 let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
                 if curr_read_curs == curr_write_curs {
                     return None; // Buffer is empty
@@ -78,43 +770,2650 @@ pub mod lock_free_ring_buffer {
                     .read_cursor
                     .compare_exchange_weak(
                         curr_read_curs,
-                        (curr_read_curs + 1) & Self::BUFFER_MASK,
+                        advance_cursor(curr_read_curs, 1),
                         Ordering::AcqRel,
                         Ordering::Relaxed,
                     )
                     .is_ok()
                 {
-                    return Some(unsafe { self.buffer[curr_read_curs as usize].get().read() });
+                    let item: T = unsafe { self.take_slot(curr_read_curs) };
+                    self.free_slot(curr_read_curs);
+                    self.wake_writer();
+                    return Some(item);
                 }
             }
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+        // Drains up to N items into a caller-owned array without allocating and
+        // returns the count. Only out[..count] is written; callers may
+        // assume_init those and must leave the rest alone.
+        pub fn try_read_into_array<const N: usize>(&self, out: &mut [MaybeUninit<T>; N]) -> usize {
+            let mut count: usize = 0;
+            while count < N {
+                match self.try_read() {
+                    Some(item) => {
+                        out[count].write(item);
+                        count += 1;
+                    }
+                    None => break,
+                }
+            }
+            count
+        }
 
-        #[test]
-        fn single_threaded_write_read() {
-            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
-            assert!(buffer.try_write(1));
-            assert!(buffer.try_write(2));
-            assert_eq!(buffer.try_read(), Some(1));
-            assert_eq!(buffer.try_read(), Some(2));
-            assert_eq!(buffer.try_read(), None);
+        // Visits the buffered items in FIFO order without consuming or cloning them.
+        // Meant for monitoring a quiescent buffer or from the single consumer; items
+        // must not be read by anyone else while f runs.
+        pub fn for_each<F: FnMut(&T)>(&self, f: F) {
+            let (head, tail): (&[T], &[T]) = self.readable_slices();
+            head.iter().chain(tail).for_each(f);
         }
 
-        #[test]
-        fn buffer_full() {
-            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
-            assert!(buffer.try_write(1));
-            assert!(buffer.try_write(2));
-            assert!(buffer.try_write(3)); 
-            assert!(!buffer.try_write(4));// Buffer should be full
-            assert_eq!(buffer.try_read(), Some(1));
-            assert!(buffer.try_write(3)); // Should succeed after reading
-            assert_eq!(buffer.try_read(), Some(2));
-            assert_eq!(buffer.try_read(), Some(3));
+        // Consumes the buffer and returns the remaining items in FIFO order
+        pub fn into_vec(self) -> Vec<T> {
+            let mut items: Vec<T> = Vec::with_capacity(self.len());
+            while let Some(item) = self.try_read() {
+                items.push(item);
+            }
+            items
+        }
+
+        // try_read that reports a producer panic, like Mutex poisoning: once every
+        // item published before the panicked frame has been read, this returns
+        // Err(Poisoned) instead of Ok(None). Writes fail from then on.
+        pub fn try_read_checked(&self) -> Result<Option<T>, Poisoned> {
+            if let Some(item) = self.try_read() {
+                return Ok(Some(item));
+            }
+            if self.is_poisoned() {
+                return Err(Poisoned);
+            }
+            Ok(None)
+        }
+
+        pub fn is_poisoned(&self) -> bool {
+            self.poisoned.load(Ordering::Acquire)
+        }
+
+        // Non-blocking read that only succeeds once at least min items are buffered,
+        // so a poller can let work accumulate before picking it up
+        pub fn try_read_if_len(&self, min: usize) -> Option<T> {
+            if self.len() < min {
+                return None;
+            }
+            self.try_read()
+        }
+
+        // Drains up to max items and folds them oldest first with combine, for
+        // mergeable payloads such as deltas. Returns None if nothing was buffered.
+        pub fn read_coalesced<F: FnMut(T, T) -> T>(&self, max: usize, mut combine: F) -> Option<T> {
+            if max == 0 {
+                return None;
+            }
+            let mut acc: T = self.try_read()?;
+            for _ in 1..max {
+                match self.try_read() {
+                    Some(item) => acc = combine(acc, item),
+                    None => break, // Buffer is empty
+                }
+            }
+            Some(acc)
+        }
+
+        // Reads oldest items into out until only target_len remain, keeping a
+        // reserve against underruns, and returns how many were drained. The excess
+        // is measured once up front, so writes racing with the drain stay buffered.
+        pub fn drain_to_level(&self, target_len: usize, out: &mut Vec<T>) -> usize {
+            let excess: usize = self.len().saturating_sub(target_len);
+            let mut drained: usize = 0;
+            while drained < excess {
+                match self.try_read() {
+                    Some(item) => out.push(item),
+                    None => break, // Another consumer got there first
+                }
+                drained += 1;
+            }
+            drained
+        }
+
+        // Wait-free read for the sole consumer of an Spsc split: the slot is taken
+        // and stamped free before the read cursor is release-stored past it
+        fn try_pop_single_consumer(&self) -> Option<T> {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            if curr_read_curs == curr_write_curs {
+                return None; // Buffer is empty
+            }
+
+            let item: T = unsafe { self.take_slot(curr_read_curs) };
+            self.free_slot(curr_read_curs);
+            self.read_cursor
+                .store(advance_cursor(curr_read_curs, 1), Ordering::Release);
+            self.wake_writer();
+            Some(item)
+        }
+
+        /// Reads the oldest item without checking that the buffer is non-empty, for
+        /// pipelines where availability is already guaranteed by other means, such
+        /// as a semaphore counting published items.
+        ///
+        /// # Safety
+        ///
+        /// - At least one item must be published: the caller's own synchronization
+        ///   must make the write of that item, and the write cursor move that
+        ///   published it, happen-before this call (e.g. a release/acquire pair).
+        ///   Reading an empty buffer hands out a stale or default slot and moves the
+        ///   read cursor past the write cursor, corrupting the buffer.
+        /// - The caller must be the only consumer while this runs: no concurrent
+        ///   `try_read`, `read_unchecked` or other read on any thread.
+        ///
+        /// Debug builds assert the buffer is non-empty.
+        pub unsafe fn read_unchecked(&self) -> T {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+            debug_assert!(
+                curr_read_curs != self.write_cursor.load(Ordering::Acquire),
+                "read_unchecked on an empty buffer"
+            );
+
+            let item: T = self.take_slot(curr_read_curs);
+            self.free_slot(curr_read_curs);
+            self.read_cursor
+                .store(advance_cursor(curr_read_curs, 1), Ordering::Release);
+            self.wake_writer();
+            item
+        }
+
+        // Stores an item into a slot the caller has claimed but not yet published
+        unsafe fn write_slot(&self, curs: u32, item: T) {
+            let index: usize = Self::slot_index(curs);
+            *self.buffer[index].get() = item; // Write the item
+        }
+
+        // Moves the item out of a slot the caller has claimed for reading. Takes
+        // rather than bit-copies so the slot never holds a second owner of the
+        // value (it is dropped when overwritten or with the buffer).
+        unsafe fn take_slot(&self, curs: u32) -> T {
+            let index: usize = Self::slot_index(curs);
+            // Debug builds check that the write behind this slot was stamped before
+            // the cursor moved, catching regressions of the publish order. Nobody
+            // restamps the slot until the caller frees it.
+            debug_assert!(
+                self.slot_seq[index].load(Ordering::Relaxed) == advance_cursor(curs, 1),
+                "read of unpublished slot {index}"
+            );
+
+            std::mem::take(&mut *self.buffer[index].get())
+        }
+
+        // Hands the contiguous readable region to f, which returns how many items it
+        // actually processed (e.g. bytes a socket accepted), then advances the read
+        // cursor by exactly that many. Must only be called from a single consumer.
+        pub fn consume_slice_with<F: FnMut(&[T]) -> usize>(&self, mut f: F) -> usize {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+            let slice: &[T] = self.readable_slice(curr_read_curs);
+            if slice.is_empty() {
+                return 0;
+            }
+
+            let consumed: usize = f(slice).min(slice.len());
+            self.advance_read(consumed);
+            consumed
+        }
+
+        // Contiguous readable region, for consumers that process items in place and
+        // then call advance_read. Must only be called from a single consumer.
+        pub fn peek_slice(&self) -> &[T] {
+            self.readable_slice(self.read_cursor.load(Ordering::Relaxed))
+        }
+
+        // Clone of the item i positions past the read cursor (0 is the oldest), or
+        // None if i >= len(). Nothing is consumed. Must only be called from a single
+        // consumer.
+        pub fn peek_at(&self, i: usize) -> Option<T>
+        where
+            T: Clone,
+        {
+            let (first, wrapped): (&[T], &[T]) = self.readable_slices();
+            match first.get(i) {
+                Some(item) => Some(item.clone()),
+                None => wrapped.get(i - first.len()).cloned(),
+            }
+        }
+
+        // Commits n items read in place. Panics if fewer than n items are buffered.
+        // Must only be called from a single consumer.
+        pub fn advance_read(&self, n: usize) {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Relaxed);
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            let available: usize = Self::used_slots(curr_write_curs, curr_read_curs) as usize;
+            assert!(n <= available, "advance_read({n}) past the {available} buffered items");
+
+            for offset in 0..n as u32 {
+                self.free_slot(advance_cursor(curr_read_curs, offset)); // Read in place, left for overwrite
+            }
+            self.read_cursor
+                .store(advance_cursor(curr_read_curs, n as u32), Ordering::Release);
+            if n > 0 {
+                self.wake_writer();
+            }
+        }
+
+        // Hands the readable region to f one contiguous chunk at a time (two chunks
+        // when it wraps). A chunk counts as accepted when f returns Continue; on
+        // Break draining stops and that chunk stays buffered, which lets a slow
+        // consumer push back. Returns the number of accepted items, which is how
+        // far the read cursor moved. Must only be called from a single consumer.
+        pub fn drain_batch_with<F: FnMut(&[T]) -> ControlFlow<()>>(&self, mut f: F) -> usize
+        where
+            T: Copy,
+        {
+            let (first, wrapped): (&[T], &[T]) = self.readable_slices();
+            let mut accepted: usize = 0;
+            for chunk in [first, wrapped] {
+                if chunk.is_empty() || f(chunk).is_break() {
+                    break;
+                }
+                accepted += chunk.len();
+            }
+            self.advance_read(accepted);
+            accepted
+        }
+
+        // Discards everything published so far while producers and consumers keep
+        // running. The read cursor is CAS-advanced to the write cursor, so an
+        // in-flight try_read either claims its slot before the clear or retries
+        // afterwards; no slot is handed out twice. The discarded slots are then
+        // stamped free as if read, while a try_read that claimed its slot before the
+        // clear frees that slot itself once done, so producers refilling the space
+        // never write into a slot it is still taking. Writes still in flight
+        // (claimed but not yet published) are not discarded: they become readable
+        // after the clear as if written after it. The epoch only counts clears and
+        // is bumped after the cursor moves, so it does not tell which side of a
+        // clear an item was written on.
+        pub fn clear_concurrent(&self) {
+            loop {
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+                let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+
+                if self
+                    .read_cursor
+                    .compare_exchange_weak(curr_read_curs, curr_write_curs, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let discarded: u32 = curr_write_curs.wrapping_sub(curr_read_curs);
+                    for offset in 0..discarded {
+                        self.free_slot(advance_cursor(curr_read_curs, offset));
+                    }
+                    self.epoch.fetch_add(1, Ordering::Release);
+                    self.wake_writer();
+                    return;
+                }
+            }
+        }
+
+        // Number of clear_concurrent calls so far
+        pub fn epoch(&self) -> u32 {
+            self.epoch.load(Ordering::Acquire)
+        }
+
+        pub fn capacity(&self) -> usize {
+            Self::CAPACITY
+        }
+
+        // Whether the cursors and flags are native atomics on this target. Rust only
+        // provides AtomicU32 and AtomicBool where the hardware supports them, so this
+        // is a compile-time fact; it exists so users on unusual targets can assert it.
+        // The waker slot used by poll_write is a Mutex either way, and the counters
+        // behind wrap_count and occupancy_histogram need 64-bit atomics.
+        pub fn is_lock_free() -> bool {
+            cfg!(all(target_has_atomic = "32", target_has_atomic = "8"))
+        }
+
+        // len, is_full and is_empty are all derived from the same cursor snapshot
+        // so they agree on the boundaries; under concurrency each call is a
+        // point-in-time value
+        pub fn len(&self) -> usize {
+            self.occupancy_snapshot().2
+        }
+
+        // Self-consistent (read index, write index, len) triple for lock-free
+        // monitoring. The write cursor is loaded on both sides of the read cursor and
+        // the pair is retried if it moved, so the read cursor is never ahead of the
+        // write cursor it is paired with. Best effort: the triple describes a real
+        // moment, which may already be stale when it is returned.
+        pub fn occupancy_snapshot(&self) -> (u32, u32, usize) {
+            loop {
+                let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+                let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+                if self.write_cursor.load(Ordering::Acquire) == curr_write_curs {
+                    let len: usize = Self::used_slots(curr_write_curs, curr_read_curs) as usize;
+                    return (
+                        curr_read_curs & Self::BUFFER_MASK,
+                        curr_write_curs & Self::BUFFER_MASK,
+                        len,
+                    );
+                }
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        pub fn is_full(&self) -> bool {
+            self.len() == self.capacity()
+        }
+
+        // Full SeqCst barrier for producers that need visibility at a well-defined
+        // point. Items handed over through the buffer never need it, the cursors
+        // already use acquire/release; it is for ordering earlier writes against a
+        // signal that bypasses the buffer, e.g. before raising an interrupt or ringing
+        // a doorbell register. Compiles to mfence on x86 and dmb ish on ARM.
+        pub fn publish_fence(&self) {
+            fence(Ordering::SeqCst);
+        }
+
+        // Current free-running write position, loaded with Acquire so slots before it
+        // are visible. The slot index is write_position() & (SIZE - 1).
+        pub fn write_position(&self) -> u32 {
+            self.write_cursor.load(Ordering::Acquire)
+        }
+
+        // Current free-running read position, loaded with Acquire so freed slots are
+        // observed. write_position().wrapping_sub(read_position()) is the item count.
+        pub fn read_position(&self) -> u32 {
+            self.read_cursor.load(Ordering::Acquire)
+        }
+
+        // Contiguous readable items starting at curr_read_curs, up to the write cursor
+        // or the end of the storage, whichever comes first
+        fn readable_slice(&self, curr_read_curs: u32) -> &[T] {
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            self.slices_between(curr_read_curs, curr_write_curs).0
+        }
+
+        // All readable items as (up to) two regions in FIFO order; the second is
+        // non-empty only when the data wraps past the end of the storage
+        fn readable_slices(&self) -> (&[T], &[T]) {
+            let curr_read_curs: u32 = self.read_cursor.load(Ordering::Acquire);
+            let curr_write_curs: u32 = self.write_cursor.load(Ordering::Acquire);
+            self.slices_between(curr_read_curs, curr_write_curs)
+        }
+
+        fn slices_between(&self, curr_read_curs: u32, curr_write_curs: u32) -> (&[T], &[T]) {
+            let start: usize = Self::slot_index(curr_read_curs);
+            let len: usize = Self::used_slots(curr_write_curs, curr_read_curs) as usize;
+            let first_len: usize = len.min(SIZE - start);
+
+            // SAFETY: UnsafeCell<T> has the same layout as T, and the slots between the
+            // read and write cursors are published and not touched by producers
+            unsafe {
+                let base: *const T = self.buffer.as_ptr() as *const T;
+                (
+                    std::slice::from_raw_parts(base.add(start), first_len),
+                    std::slice::from_raw_parts(base, len - first_len),
+                )
+            }
+        }
+    }
+
+    impl<const SIZE: usize> RingBuffer<u8, SIZE> {
+        // The readable bytes as two regions for write_vectored or sendmsg, in order;
+        // the second is empty unless the data wraps. Nothing is consumed: call
+        // advance_read with the bytes actually written. Must only be called from a
+        // single consumer.
+        pub fn readable_io_slices(&self) -> [IoSlice<'_>; 2] {
+            let (first, wrapped): (&[u8], &[u8]) = self.readable_slices();
+            [IoSlice::new(first), IoSlice::new(wrapped)]
+        }
+    }
+
+    struct Channel<T, const SIZE: usize> {
+        ring: RingBuffer<T, SIZE>,
+        producers: AtomicUsize, // Live Producer handles
+    }
+
+    // Producer-side copy of the read cursor for the Spsc write path. Atomics rather
+    // than Cells so an Mpmc Producer, which never touches it, stays Sync.
+    struct ReadCursorCache {
+        read_curs: AtomicU32,
+        writes_since_refresh: AtomicU32,
+        refresh_interval: u32, // Writes allowed on the cached cursor before a reload
+    }
+
+    impl ReadCursorCache {
+        fn new(read_curs: u32) -> Self {
+            ReadCursorCache {
+                read_curs: AtomicU32::new(read_curs),
+                writes_since_refresh: AtomicU32::new(0),
+                refresh_interval: 0,
+            }
+        }
+    }
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    // Type-level marker for how many threads may use each end of a split buffer
+    pub trait Mode: sealed::Sealed {
+        #[doc(hidden)]
+        fn pop<T: Default, const SIZE: usize>(ring: &RingBuffer<T, SIZE>) -> Option<T>;
+    }
+
+    // One producer and one consumer. The handles are neither Clone nor Sync, so
+    // each end can use the wait-free path that owns its cursor.
+    pub struct Spsc {
+        _not_sync: PhantomData<Cell<()>>,
+    }
+
+    // Any number of producers and consumers; the handles are Clone and use the CAS
+    // paths. Consumers free their slots independently: while one is still taking
+    // an item, others read on past it, and producers see that one slot as full
+    // until it is freed.
+    pub struct Mpmc;
+
+    impl sealed::Sealed for Spsc {}
+    impl sealed::Sealed for Mpmc {}
+
+    impl Mode for Spsc {
+        fn pop<T: Default, const SIZE: usize>(ring: &RingBuffer<T, SIZE>) -> Option<T> {
+            ring.try_pop_single_consumer()
+        }
+    }
+
+    impl Mode for Mpmc {
+        fn pop<T: Default, const SIZE: usize>(ring: &RingBuffer<T, SIZE>) -> Option<T> {
+            ring.try_read()
+        }
+    }
+
+    pub struct Producer<T, const SIZE: usize, M = Mpmc> {
+        channel: Arc<Channel<T, SIZE>>,
+        read_cache: ReadCursorCache,
+        _mode: PhantomData<M>,
+    }
+
+    pub struct Consumer<T, const SIZE: usize, M = Mpmc> {
+        channel: Arc<Channel<T, SIZE>>,
+        _mode: PhantomData<M>,
+    }
+
+    // Both ends of a split, as handed back by a failed reunite
+    pub type SplitHalves<T, const SIZE: usize, M = Mpmc> = (Producer<T, SIZE, M>, Consumer<T, SIZE, M>);
+
+    impl<T, const SIZE: usize, M: Mode> Producer<T, SIZE, M>
+    where
+        T: Default,
+    {
+        fn new(channel: Arc<Channel<T, SIZE>>) -> Self {
+            let read_curs: u32 = channel.ring.read_cursor.load(Ordering::Acquire);
+            Producer {
+                channel,
+                read_cache: ReadCursorCache::new(read_curs),
+                _mode: PhantomData,
+            }
+        }
+    }
+
+    impl<T, const SIZE: usize> Producer<T, SIZE, Mpmc>
+    where
+        T: Default,
+    {
+        pub fn try_write(&self, item: T) -> bool {
+            self.channel.ring.try_push(item).is_ok()
+        }
+    }
+
+    impl<T, const SIZE: usize> Producer<T, SIZE, Spsc>
+    where
+        T: Default,
+    {
+        pub fn try_write(&self, item: T) -> bool {
+            self.channel
+                .ring
+                .try_push_single_producer(item, &self.read_cache)
+                .is_ok()
+        }
+
+        // Number of writes that may reuse the cached read cursor before it is
+        // reloaded from the ring. 0, the default, reloads on every write; larger
+        // values save cross-core traffic at the cost of seeing freed space later.
+        // A write that finds the buffer apparently full always reloads.
+        pub fn set_read_refresh_interval(&mut self, writes: u32) {
+            self.read_cache.refresh_interval = writes;
+        }
+    }
+
+    impl<T, const SIZE: usize> Clone for Producer<T, SIZE, Mpmc> {
+        fn clone(&self) -> Self {
+            self.channel.producers.fetch_add(1, Ordering::Relaxed);
+            let read_curs: u32 = self.channel.ring.read_cursor.load(Ordering::Acquire);
+            Producer {
+                channel: self.channel.clone(),
+                read_cache: ReadCursorCache::new(read_curs),
+                _mode: PhantomData,
+            }
+        }
+    }
+
+    impl<T, const SIZE: usize, M> Drop for Producer<T, SIZE, M> {
+        fn drop(&mut self) {
+            // Release so the consumer sees every write made before the disconnect
+            self.channel.producers.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    impl<T, const SIZE: usize, M: Mode> Consumer<T, SIZE, M>
+    where
+        T: Default,
+    {
+        fn new(channel: Arc<Channel<T, SIZE>>) -> Self {
+            Consumer {
+                channel,
+                _mode: PhantomData,
+            }
+        }
+
+        pub fn try_read(&self) -> Option<T> {
+            M::pop(&self.channel.ring)
+        }
+
+        pub fn try_read_checked(&self) -> Result<Option<T>, Poisoned> {
+            if let Some(item) = self.try_read() {
+                return Ok(Some(item));
+            }
+            if self.channel.ring.is_poisoned() {
+                return Err(Poisoned);
+            }
+            Ok(None)
+        }
+
+        // Three-state read for event loops: Ok(Some) is an item, Ok(None) means empty
+        // for now, and Err(Disconnected) means every producer is gone and the buffer
+        // has been drained
+        pub fn try_next(&self) -> Result<Option<T>, Disconnected> {
+            if let Some(item) = self.try_read() {
+                return Ok(Some(item));
+            }
+            if self.channel.producers.load(Ordering::Acquire) == 0 {
+                // Producers may have written right before dropping
+                return self.try_read().map(Some).ok_or(Disconnected);
+            }
+            Ok(None)
+        }
+
+        // Blocking read: waits for an item and returns Ok(Some) with it. Ok(None)
+        // means every producer is dropped and the buffer has been drained, and
+        // Err(Poisoned) that a producer panicked and the items before its frame
+        // have been drained.
+        pub fn read(&self) -> Result<Option<T>, Poisoned> {
+            let mut step: u32 = 0;
+            loop {
+                match self.try_next() {
+                    Ok(Some(item)) => return Ok(Some(item)),
+                    // A panicking producer is dropped too; report why it left
+                    _ if self.channel.ring.is_poisoned() => return Err(Poisoned),
+                    Err(Disconnected) => return Ok(None),
+                    Ok(None) => backoff(&mut step),
+                }
+            }
+        }
+
+        // Blocking iterator over read that ends on a clean disconnect and on
+        // poisoning alike; use read to tell the two apart
+        pub fn iter(&self) -> BlockingIter<'_, T, SIZE, M> {
+            BlockingIter { consumer: self }
+        }
+    }
+
+    impl<T, const SIZE: usize> Clone for Consumer<T, SIZE, Mpmc> {
+        fn clone(&self) -> Self {
+            Consumer {
+                channel: self.channel.clone(),
+                _mode: PhantomData,
+            }
+        }
+    }
+
+    pub struct BlockingIter<'a, T, const SIZE: usize, M = Mpmc> {
+        consumer: &'a Consumer<T, SIZE, M>,
+    }
+
+    impl<T, const SIZE: usize, M: Mode> Iterator for BlockingIter<'_, T, SIZE, M>
+    where
+        T: Default,
+    {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.consumer.read().ok().flatten()
+        }
+    }
+
+    // LifoRingBuffer slot states. Claiming a position on top_cursor does not make
+    // its slot free: the previous owner may still be moving its item in or out.
+    const SLOT_EMPTY: u8 = 0;
+    const SLOT_BUSY: u8 = 1; // Being written or taken by the thread that claimed it
+    const SLOT_FULL: u8 = 2;
+
+    pub struct LifoRingBuffer<T, const SIZE: usize> {
+        buffer: [UnsafeCell<T>; SIZE], // Buffer storage
+        slot_state: [AtomicU8; SIZE],  // SLOT_EMPTY, SLOT_BUSY or SLOT_FULL per slot
+        top_cursor: AtomicU32,         // Number of occupied slots
+    }
+
+    // SAFETY: Thread-safe when T is Send because:
+    // - The top cursor hands each slot position to one claimant at a time
+    // - A slot is only touched by the thread that moved its state to SLOT_BUSY,
+    //   so a pop never reads a slot a push is still writing and vice versa
+    unsafe impl<T: Send, const SIZE: usize> Sync for LifoRingBuffer<T, SIZE> {}
+
+    impl<T, const SIZE: usize> Default for LifoRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    // Stack (LIFO) counterpart of RingBuffer: reads return the newest item first.
+    // Holds up to SIZE items; nothing is masked, so SIZE need not be a power of
+    // two. Unlike RingBuffer this is not lock-free. A push and a pop that race for
+    // the same top position take turns on its slot state, so a thread preempted
+    // between moving top_cursor and finishing its write or take blocks the next
+    // claimant of that slot until it runs again; each slot is in effect a small
+    // lock. Other slots are unaffected.
+    impl<T, const SIZE: usize> LifoRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        pub fn new() -> Self {
+            LifoRingBuffer {
+                slot_state: std::array::from_fn(|_| AtomicU8::new(SLOT_EMPTY)),
+                top_cursor: AtomicU32::new(0),
+                buffer: std::array::from_fn(|_| UnsafeCell::new(T::default())),
+            }
+        }
+
+        pub fn try_write(&self, item: T) -> bool {
+            loop {
+                let curr_top_curs: u32 = self.top_cursor.load(Ordering::Acquire);
+                if curr_top_curs as usize == SIZE {
+                    return false; // Buffer is full
+                }
+
+                if self
+                    .top_cursor
+                    .compare_exchange_weak(curr_top_curs, curr_top_curs + 1, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let index: usize = curr_top_curs as usize;
+                    self.lock_slot(index, SLOT_EMPTY);
+                    unsafe {
+                        *self.buffer[index].get() = item; // Write the item
+                    }
+                    self.slot_state[index].store(SLOT_FULL, Ordering::Release);
+                    return true; // Write successful
+                }
+            }
+        }
+
+        // Reads the most recently written item and moves the top cursor down
+        pub fn pop_newest(&self) -> Option<T> {
+            loop {
+                let curr_top_curs: u32 = self.top_cursor.load(Ordering::Acquire);
+                if curr_top_curs == 0 {
+                    return None; // Buffer is empty
+                }
+
+                let next_top_curs: u32 = curr_top_curs - 1;
+                if self
+                    .top_cursor
+                    .compare_exchange_weak(curr_top_curs, next_top_curs, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let index: usize = next_top_curs as usize;
+                    self.lock_slot(index, SLOT_FULL);
+                    let item: T = unsafe { std::mem::take(&mut *self.buffer[index].get()) };
+                    self.slot_state[index].store(SLOT_EMPTY, Ordering::Release);
+                    return Some(item);
+                }
+            }
+        }
+
+        // Waits for a claimed slot to reach the expected state and marks it busy.
+        // The wait lasts while the slot's previous claimant, which moved the top
+        // cursor first, finishes its own write or take, however long that thread
+        // is descheduled; backoff parks the waiter meanwhile.
+        fn lock_slot(&self, index: usize, expected: u8) {
+            let mut step: u32 = 0;
+            while self.slot_state[index]
+                .compare_exchange_weak(expected, SLOT_BUSY, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                backoff(&mut step);
+            }
+        }
+    }
+
+    // Single-threaded counterpart of RingBuffer for payloads that are not Send,
+    // such as Rc or RefCell values queued inside one scheduler thread. Same FIFO
+    // cursors and SIZE - 1 capacity, but held in Cells: no atomics, and !Sync.
+    pub struct LocalRingBuffer<T, const SIZE: usize> {
+        buffer: [Cell<T>; SIZE], // Buffer storage
+        write_cursor: Cell<u32>, // Next slot to write
+        read_cursor: Cell<u32>,  // Next slot to read
+    }
+
+    impl<T, const SIZE: usize> Default for LocalRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T, const SIZE: usize> LocalRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        const BUFFER_MASK: u32 = (SIZE - 1) as u32;
+
+        pub fn new() -> Self {
+            const {
+                assert!(is_power_of_two(SIZE), "Size must be a power of two");
+            }
+            LocalRingBuffer {
+                buffer: std::array::from_fn(|_| Cell::new(T::default())),
+                write_cursor: Cell::new(0),
+                read_cursor: Cell::new(0),
+            }
+        }
+
+        pub fn try_write(&self, item: T) -> bool {
+            let curr_write_curs: u32 = self.write_cursor.get();
+            let next_write_curs: u32 = advance_cursor(curr_write_curs, 1) & Self::BUFFER_MASK;
+
+            if next_write_curs == self.read_cursor.get() {
+                return false; // Buffer is full
+            }
+
+            self.buffer[curr_write_curs as usize].set(item); // Write the item
+            self.write_cursor.set(next_write_curs);
+            true // Write successful
+        }
+
+        pub fn try_read(&self) -> Option<T> {
+            let curr_read_curs: u32 = self.read_cursor.get();
+            if curr_read_curs == self.write_cursor.get() {
+                return None; // Buffer is empty
+            }
+
+            self.read_cursor
+                .set(advance_cursor(curr_read_curs, 1) & Self::BUFFER_MASK);
+            Some(self.buffer[curr_read_curs as usize].take())
+        }
+
+        pub fn len(&self) -> usize {
+            (self.write_cursor.get().wrapping_sub(self.read_cursor.get()) & Self::BUFFER_MASK) as usize
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+    }
+
+    // Returned by Consumer::try_next once every producer is dropped and the buffer
+    // has been drained
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Disconnected;
+
+    impl fmt::Display for Disconnected {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("all producers disconnected")
+        }
+    }
+
+    impl std::error::Error for Disconnected {}
+
+    // Returned once a poisoned buffer has been drained up to the frame whose
+    // producer panicked
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Poisoned;
+
+    impl fmt::Display for Poisoned {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("ring buffer poisoned by a panicking producer")
+        }
+    }
+
+    impl std::error::Error for Poisoned {}
+
+    // Contiguous slots claimed by try_reserve_n. The slots hold stale or default
+    // values until overwritten; they are published when the guard is committed or
+    // dropped, except that dropping it during a panic poisons the buffer instead.
+    pub struct WriteReservation<'a, T: Default, const SIZE: usize> {
+        ring: &'a RingBuffer<T, SIZE>,
+        start: u32, // Position of the first claimed slot
+        len: u32,   // Number of claimed slots
+    }
+
+    impl<T: Default, const SIZE: usize> WriteReservation<'_, T, SIZE> {
+        // The claimed slots in order; the second region is non-empty only when the
+        // reservation wraps past the end of the storage
+        pub fn slices(&mut self) -> (&mut [T], &mut [T]) {
+            let start: usize = RingBuffer::<T, SIZE>::slot_index(self.start);
+            let first_len: usize = (self.len as usize).min(SIZE - start);
+
+            // SAFETY: UnsafeCell<T> has the same layout as T, and claimed slots are
+            // not touched by consumers or other producers until published
+            unsafe {
+                let base: *mut T = self.ring.buffer.as_ptr() as *mut T;
+                (
+                    std::slice::from_raw_parts_mut(base.add(start), first_len),
+                    std::slice::from_raw_parts_mut(base, self.len as usize - first_len),
+                )
+            }
+        }
+
+        // Publishes the frame; same as dropping the guard
+        pub fn commit(self) {}
+    }
+
+    impl<T: Default, const SIZE: usize> Drop for WriteReservation<'_, T, SIZE> {
+        fn drop(&mut self) {
+            if thread::panicking() {
+                // The frame may be half written: never publish it, poison instead
+                self.ring.poison(self.start);
+                return;
+            }
+
+            self.ring.publish(self.start, self.len);
+        }
+    }
+
+    // Front/back pair of rings: producers fill the back ring through try_write
+    // while the consumer drains the front, and swap() flips their roles. Writes
+    // never reach the ring being handed over once swap returns, so the consumer
+    // drains a stable snapshot. The reference returned by front() names a ring,
+    // not a role: fetch it again after each swap.
+    pub struct DoubleBuffer<T, const SIZE: usize> {
+        buffers: [RingBuffer<T, SIZE>; 2],
+        writers: [AtomicUsize; 2], // Writes in progress per ring, waited out by swap
+        front: AtomicBool,         // false: buffers[0] is the front ring
+    }
+
+    impl<T, const SIZE: usize> Default for DoubleBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T, const SIZE: usize> DoubleBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        pub fn new() -> Self {
+            DoubleBuffer {
+                buffers: [RingBuffer::new(), RingBuffer::new()],
+                writers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+                front: AtomicBool::new(false),
+            }
+        }
+
+        // Ring the consumer drains
+        pub fn front(&self) -> &RingBuffer<T, SIZE> {
+            &self.buffers[self.front.load(Ordering::Acquire) as usize]
+        }
+
+        // Writes into the back ring, returning false if it is full. The write is
+        // registered on the ring before checking that it is still the back, so a
+        // concurrent swap either waits for it or makes it retry on the new back.
+        pub fn try_write(&self, item: T) -> bool {
+            loop {
+                let back: usize = !self.front.load(Ordering::SeqCst) as usize;
+                self.writers[back].fetch_add(1, Ordering::SeqCst);
+                if !self.front.load(Ordering::SeqCst) as usize != back {
+                    self.writers[back].fetch_sub(1, Ordering::Release);
+                    continue; // Swapped in between, this ring is now the front
+                }
+
+                let written: bool = self.buffers[back].try_write(item);
+                self.writers[back].fetch_sub(1, Ordering::Release);
+                return written;
+            }
+        }
+
+        // Flips front and back once the front has been drained, returning the
+        // freshly filled ring as the new front, or None without flipping while the
+        // front still holds items. Blocks until writes that registered on the old
+        // back before the flip have finished. Must only be called from a single
+        // thread, normally the consumer.
+        pub fn swap(&self) -> Option<&RingBuffer<T, SIZE>> {
+            if !self.front().is_empty() {
+                return None; // Flipping now would mix the old front into the back
+            }
+            let new_front: usize = !self.front.fetch_xor(true, Ordering::SeqCst) as usize;
+
+            // SeqCst pairs with try_write: a writer either sees the flip or is counted here
+            let mut step: u32 = 0;
+            while self.writers[new_front].load(Ordering::SeqCst) != 0 {
+                backoff(&mut step);
+            }
+            Some(&self.buffers[new_front])
+        }
+    }
+
+    // Message pump that recycles boxed payloads instead of allocating per message.
+    // A free-index ring hands payload slots to producers, the ready ring carries
+    // published slots to consumers, and dropping a consumed slot returns it to the
+    // free ring. All SIZE - 1 usable payloads are allocated up front.
+    pub struct PooledRingBuffer<T, const SIZE: usize> {
+        payloads: [UnsafeCell<Box<T>>; SIZE], // The last box is never handed out
+        free: RingBuffer<usize, SIZE>,        // Indices of payloads nobody holds
+        ready: RingBuffer<usize, SIZE>,       // Indices of published payloads
+    }
+
+    // SAFETY: A payload index lives in exactly one place at a time (free ring,
+    // ready ring or a single handle), so each payload has one user at a time
+    unsafe impl<T: Send, const SIZE: usize> Sync for PooledRingBuffer<T, SIZE> {}
+
+    impl<T, const SIZE: usize> Default for PooledRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T, const SIZE: usize> PooledRingBuffer<T, SIZE>
+    where
+        T: Default,
+    {
+        pub fn new() -> Self {
+            let free: RingBuffer<usize, SIZE> = RingBuffer::new();
+            for index in 0..SIZE - 1 {
+                free.try_write(index);
+            }
+            PooledRingBuffer {
+                payloads: std::array::from_fn(|_| UnsafeCell::new(Box::default())),
+                free,
+                ready: RingBuffer::new(),
+            }
+        }
+
+        // Takes a recycled payload to fill, or None if every payload is in use.
+        // The payload still holds whatever its previous user left in it.
+        pub fn acquire(&self) -> Option<PoolSlot<'_, T, SIZE>> {
+            let index: usize = self.free.try_read()?;
+            Some(PoolSlot { pool: self, index })
+        }
+
+        // Takes the oldest published payload; dropping it recycles the payload
+        pub fn consume(&self) -> Option<PoolItem<'_, T, SIZE>> {
+            let index: usize = self.ready.try_read()?;
+            Some(PoolItem { pool: self, index })
+        }
+
+        fn recycle(&self, index: usize) {
+            // Only SIZE - 1 indices exist, so there is always room
+            let recycled: bool = self.free.try_write(index);
+            debug_assert!(recycled);
+        }
+    }
+
+    // Payload acquired by a producer; dropping it without publishing recycles it
+    pub struct PoolSlot<'a, T: Default, const SIZE: usize> {
+        pool: &'a PooledRingBuffer<T, SIZE>,
+        index: usize,
+    }
+
+    impl<T: Default, const SIZE: usize> PoolSlot<'_, T, SIZE> {
+        // Enqueues the payload for consumers
+        pub fn publish(self) {
+            let published: bool = self.pool.ready.try_write(self.index);
+            debug_assert!(published);
+            std::mem::forget(self);
+        }
+    }
+
+    impl<T: Default, const SIZE: usize> Deref for PoolSlot<'_, T, SIZE> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.pool.payloads[self.index].get() }
+        }
+    }
+
+    impl<T: Default, const SIZE: usize> DerefMut for PoolSlot<'_, T, SIZE> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.pool.payloads[self.index].get() }
+        }
+    }
+
+    impl<T: Default, const SIZE: usize> Drop for PoolSlot<'_, T, SIZE> {
+        fn drop(&mut self) {
+            self.pool.recycle(self.index);
+        }
+    }
+
+    // Payload taken by a consumer; dropping it hands the payload back to producers
+    pub struct PoolItem<'a, T: Default, const SIZE: usize> {
+        pool: &'a PooledRingBuffer<T, SIZE>,
+        index: usize,
+    }
+
+    impl<T: Default, const SIZE: usize> Deref for PoolItem<'_, T, SIZE> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.pool.payloads[self.index].get() }
+        }
+    }
+
+    impl<T: Default, const SIZE: usize> DerefMut for PoolItem<'_, T, SIZE> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.pool.payloads[self.index].get() }
+        }
+    }
+
+    impl<T: Default, const SIZE: usize> Drop for PoolItem<'_, T, SIZE> {
+        fn drop(&mut self) {
+            self.pool.recycle(self.index);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::alloc::{GlobalAlloc, Layout, System};
+
+        // Counts allocations per thread so tests can assert allocation-free paths
+        struct CountingAlloc;
+
+        thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        unsafe impl GlobalAlloc for CountingAlloc {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static GLOBAL: CountingAlloc = CountingAlloc;
+
+        fn allocations() -> usize {
+            ALLOCATIONS.with(Cell::get)
+        }
+
+        #[test]
+        fn single_threaded_write_read() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            assert!(buffer.try_write(2));
+            assert_eq!(buffer.try_read(), Some(1));
+            assert_eq!(buffer.try_read(), Some(2));
+            assert_eq!(buffer.try_read(), None);
+        }
+
+        #[test]
+        fn buffer_full() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            assert!(buffer.try_write(2));
+            assert!(buffer.try_write(3)); 
+            assert!(!buffer.try_write(4));// Buffer should be full
+            assert_eq!(buffer.try_read(), Some(1));
+            assert!(buffer.try_write(3)); // Should succeed after reading
+            assert_eq!(buffer.try_read(), Some(2));
+            assert_eq!(buffer.try_read(), Some(3));
+        }
+
+        #[test]
+        fn try_new_rejects_non_power_of_two() {
+            assert!(RingBuffer::<i32, 3>::try_new().is_none());
+            let buffer = RingBuffer::<i32, 4>::try_new().expect("4 is a power of two");
+            assert!(buffer.try_write(1));
+            assert_eq!(buffer.try_read(), Some(1));
+        }
+
+        #[test]
+        fn lifo_pop_newest() {
+            let buffer: LifoRingBuffer<i32, 3> = LifoRingBuffer::new();
+            assert!(buffer.try_write(1));
+            assert!(buffer.try_write(2));
+            assert!(buffer.try_write(3));
+            assert!(!buffer.try_write(4)); // Full at SIZE items
+            assert_eq!(buffer.pop_newest(), Some(3));
+            assert_eq!(buffer.pop_newest(), Some(2));
+            assert_eq!(buffer.pop_newest(), Some(1));
+            assert_eq!(buffer.pop_newest(), None);
+        }
+
+        #[test]
+        fn lifo_concurrent_push_pop_delivers_each_item_once() {
+            const NUM_ITEMS: usize = 100_000;
+            let buffer: Arc<LifoRingBuffer<usize, 4>> = Arc::new(LifoRingBuffer::new());
+
+            let producer = {
+                let buffer = Arc::clone(&buffer);
+                thread::spawn(move || {
+                    // Items start at 1 so a lost write shows up as the default 0
+                    for i in 1..=NUM_ITEMS {
+                        while !buffer.try_write(i) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            };
+
+            let mut seen: Vec<bool> = vec![false; NUM_ITEMS + 1];
+            for _ in 0..NUM_ITEMS {
+                let item: usize = loop {
+                    if let Some(item) = buffer.pop_newest() {
+                        break item;
+                    }
+                    thread::yield_now();
+                };
+                assert!(item != 0 && !seen[item], "item {item} lost or delivered twice");
+                seen[item] = true;
+            }
+            producer.join().unwrap();
+            assert_eq!(buffer.pop_newest(), None);
+        }
+
+        #[test]
+        fn lifo_pop_waits_for_claimed_slot_to_be_written() {
+            let buffer: Arc<LifoRingBuffer<i32, 4>> = Arc::new(LifoRingBuffer::new());
+            // A push that claimed slot 0 and stalled before writing it
+            buffer.top_cursor.store(1, Ordering::Release);
+
+            let consumer = {
+                let buffer = Arc::clone(&buffer);
+                thread::spawn(move || buffer.pop_newest())
+            };
+            thread::sleep(Duration::from_millis(20));
+            assert!(!consumer.is_finished()); // Does not take the slot mid-write
+
+            buffer.lock_slot(0, SLOT_EMPTY);
+            unsafe { *buffer.buffer[0].get() = 7 };
+            buffer.slot_state[0].store(SLOT_FULL, Ordering::Release);
+            assert_eq!(consumer.join().unwrap(), Some(7));
+        }
+
+        #[test]
+        fn consume_slice_with_partial() {
+            let buffer: RingBuffer<u8, 8> = RingBuffer::new();
+            for byte in 1..=6 {
+                assert!(buffer.try_write(byte));
+            }
+            // Pretend the socket only accepted half of what was offered
+            let consumed = buffer.consume_slice_with(|slice| {
+                assert_eq!(slice, &[1, 2, 3, 4, 5, 6]);
+                slice.len() / 2
+            });
+            assert_eq!(consumed, 3);
+            assert_eq!(buffer.read_cursor.load(Ordering::Relaxed), 3);
+            assert_eq!(buffer.try_read(), Some(4));
+        }
+
+        #[test]
+        fn positions_advance_and_wrap() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            for round in 0..8u32 {
+                assert_eq!(buffer.write_position(), round);
+                assert!(buffer.try_write(round as i32));
+                assert_eq!(buffer.write_position(), round + 1);
+                assert_eq!(buffer.write_position().wrapping_sub(buffer.read_position()), 1);
+
+                assert_eq!(buffer.read_position(), round);
+                assert_eq!(buffer.try_read(), Some(round as i32));
+                assert_eq!(buffer.read_position(), round + 1);
+                assert_eq!(buffer.read_position() & 3, (round + 1) % 4); // Slot index wraps
+            }
+        }
+
+        #[test]
+        fn clear_concurrent_with_running_consumer() {
+            for _ in 0..100 {
+                let buffer: Arc<RingBuffer<u32, 64>> = Arc::new(RingBuffer::new());
+                for i in 0..63 {
+                    assert!(buffer.try_write(i));
+                }
+
+                let consumer = {
+                    let buffer = buffer.clone();
+                    thread::spawn(move || {
+                        let mut seen = Vec::new();
+                        while let Some(val) = buffer.try_read() {
+                            seen.push(val);
+                        }
+                        seen
+                    })
+                };
+                buffer.clear_concurrent();
+                let seen = consumer.join().unwrap();
+
+                // Every value read exactly once, in order, and nothing after the clear
+                assert!(seen.iter().copied().eq(0..seen.len() as u32));
+                assert_eq!(buffer.try_read(), None);
+                assert_eq!(buffer.epoch(), 1);
+
+                // New operations start fresh
+                assert!(buffer.try_write(100));
+                assert_eq!(buffer.try_read(), Some(100));
+            }
+        }
+
+        #[test]
+        fn clear_concurrent_keeps_in_flight_write() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            let mut reservation = buffer.try_reserve_n(1).expect("space for the frame");
+            reservation.slices().0[0] = 2;
+            buffer.clear_concurrent();
+            assert_eq!(buffer.try_read(), None);
+
+            // Published after the clear, so it survives it
+            drop(reservation);
+            assert_eq!(buffer.try_read(), Some(2));
+        }
+
+        #[test]
+        fn clear_concurrent_with_running_producer() {
+            const NUM_ITEMS: u32 = 1_000;
+            for _ in 0..20 {
+                let buffer: Arc<RingBuffer<u32, 16>> = Arc::new(RingBuffer::new());
+                let producer = {
+                    let buffer = buffer.clone();
+                    thread::spawn(move || {
+                        for i in 0..NUM_ITEMS {
+                            while !buffer.try_write(i) {
+                                thread::yield_now();
+                            }
+                        }
+                    })
+                };
+
+                let mut seen: Vec<u32> = Vec::new();
+                let mut clears: u32 = 0;
+                while !producer.is_finished() {
+                    buffer.clear_concurrent();
+                    clears += 1;
+                    seen.extend(buffer.try_read());
+                    thread::yield_now();
+                }
+                producer.join().unwrap();
+                seen.extend(std::iter::from_fn(|| buffer.try_read()));
+
+                // A clear drops items but never hands one out twice or out of order
+                assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+                assert!(seen.iter().all(|&val| val < NUM_ITEMS));
+                assert_eq!(buffer.epoch(), clears);
+                assert!(buffer.is_empty());
+            }
+        }
+
+        #[test]
+        fn clear_concurrent_keeps_producers_out_of_a_slot_being_read() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            for i in 1..=3 {
+                assert!(buffer.try_write(i));
+            }
+            // A try_read that claimed position 0 and stalled before taking the slot
+            buffer.read_cursor.store(1, Ordering::Release);
+            buffer.clear_concurrent(); // Frees positions 1 and 2
+
+            assert!(buffer.try_write(4)); // Position 3
+            assert!(!buffer.try_write(5)); // Position 4 reuses slot 0, still being read
+
+            assert_eq!(unsafe { buffer.take_slot(0) }, 1);
+            buffer.free_slot(0);
+            assert!(buffer.try_write(5));
+            assert_eq!(buffer.try_read(), Some(4));
+            assert_eq!(buffer.try_read(), Some(5));
+        }
+
+        #[test]
+        fn clear_concurrent_races_producer_and_consumer() {
+            const NUM_ITEMS: u32 = 20_000;
+            let buffer: Arc<RingBuffer<Vec<u32>, 4>> = Arc::new(RingBuffer::new());
+            let done: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+
+            let producer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    for i in 0..NUM_ITEMS {
+                        // Heap payloads, so an overwrite mid-take shows up as a torn
+                        // vec or a double free
+                        while !buffer.try_write(vec![i; 4]) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            };
+            let consumer = {
+                let buffer = buffer.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    let mut last: Option<u32> = None;
+                    while !done.load(Ordering::Acquire) || !buffer.is_empty() {
+                        let Some(item) = buffer.try_read() else {
+                            thread::yield_now();
+                            continue;
+                        };
+                        assert_eq!(item.len(), 4, "read a slot that was not written");
+                        assert!(item.iter().all(|&val| val == item[0]), "torn item {item:?}");
+                        assert!(last < Some(item[0]), "item {} read twice or out of order", item[0]);
+                        last = Some(item[0]);
+                    }
+                })
+            };
+            let clearer = {
+                let buffer = buffer.clone();
+                let done = done.clone();
+                thread::spawn(move || {
+                    while !done.load(Ordering::Acquire) {
+                        buffer.clear_concurrent();
+                        thread::yield_now();
+                    }
+                })
+            };
+
+            producer.join().unwrap();
+            done.store(true, Ordering::Release);
+            clearer.join().unwrap();
+            consumer.join().unwrap();
+        }
+
+        #[test]
+        fn len_full_empty_agree_at_capacity() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.is_empty());
+            assert!(!buffer.is_full());
+
+            for i in 0..buffer.capacity() as i32 {
+                assert!(buffer.try_write(i));
+            }
+            assert!(buffer.is_full());
+            assert_eq!(buffer.len(), buffer.capacity());
+            assert!(!buffer.is_empty());
+
+            // Same boundaries after the cursors wrapped
+            for i in 0..5 {
+                assert_eq!(buffer.try_read(), Some(i));
+                assert!(buffer.try_write(i + 10));
+            }
+            assert!(buffer.is_full());
+            assert_eq!(buffer.len(), 7);
+        }
+
+        #[test]
+        fn consumer_iter_ends_on_producer_drop() {
+            const NUM_ITEMS: usize = 1000;
+            let (producer, consumer) = RingBuffer::<usize, 8>::new().split();
+
+            let producer = thread::spawn(move || {
+                for i in 0..NUM_ITEMS {
+                    while !producer.try_write(i) {}
+                }
+                // producer dropped here, ending the consumer's loop
+            });
+
+            let mut received = Vec::new();
+            for item in consumer.iter() {
+                received.push(item);
+            }
+            producer.join().unwrap();
+            assert!(received.into_iter().eq(0..NUM_ITEMS));
+        }
+
+        #[test]
+        fn try_read_if_len_waits_for_threshold() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            assert!(buffer.try_write(2));
+            assert_eq!(buffer.try_read_if_len(3), None);
+            assert_eq!(buffer.len(), 2);
+
+            assert!(buffer.try_write(3));
+            assert_eq!(buffer.try_read_if_len(3), Some(1));
+        }
+
+        #[test]
+        fn into_vec_returns_remaining_in_order() {
+            let buffer: RingBuffer<String, 4> = RingBuffer::new();
+            assert!(buffer.try_write("a".to_string()));
+            assert!(buffer.try_write("b".to_string()));
+            assert_eq!(buffer.try_read().as_deref(), Some("a"));
+            assert!(buffer.try_write("c".to_string()));
+            assert!(buffer.try_write("d".to_string()));
+
+            assert_eq!(buffer.into_vec(), vec!["b", "c", "d"]);
+        }
+
+        #[test]
+        fn double_buffer_swap_hands_over_back() {
+            let double: DoubleBuffer<i32, 8> = DoubleBuffer::new();
+            for i in 0..3 {
+                assert!(double.try_write(i));
+            }
+            assert_eq!(double.front().try_read(), None);
+
+            let front = double.swap().expect("front is drained");
+            // Written after the swap, so it lands in the new back ring
+            assert!(double.try_write(99));
+            assert!(double.swap().is_none()); // The front still holds 0..3
+
+            let drained: Vec<i32> = std::iter::from_fn(|| front.try_read()).collect();
+            assert_eq!(drained, vec![0, 1, 2]);
+            assert!(double.front().is_empty());
+            let front = double.swap().expect("front is drained");
+            assert_eq!(front.try_read(), Some(99));
+        }
+
+        #[test]
+        fn double_buffer_swap_waits_for_writes_in_progress() {
+            let double: Arc<DoubleBuffer<i32, 8>> = Arc::new(DoubleBuffer::new());
+            // A try_write that registered on the back ring and stalled before writing
+            double.writers[1].fetch_add(1, Ordering::SeqCst);
+
+            let consumer = {
+                let double = double.clone();
+                thread::spawn(move || double.swap().map(|front| front.try_read()))
+            };
+            thread::sleep(Duration::from_millis(20));
+            assert!(!consumer.is_finished()); // Does not hand over a ring mid-write
+
+            assert!(double.buffers[1].try_write(7));
+            double.writers[1].fetch_sub(1, Ordering::Release);
+            assert_eq!(consumer.join().unwrap(), Some(Some(7)));
+        }
+
+        #[test]
+        fn double_buffer_swaps_under_a_running_producer() {
+            const NUM_ITEMS: i32 = 20_000;
+            let double: Arc<DoubleBuffer<i32, 64>> = Arc::new(DoubleBuffer::new());
+            let producer = {
+                let double = double.clone();
+                thread::spawn(move || {
+                    for i in 0..NUM_ITEMS {
+                        while !double.try_write(i) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            };
+
+            let mut received: Vec<i32> = Vec::new();
+            while received.len() < NUM_ITEMS as usize {
+                match double.front().try_read() {
+                    Some(item) => received.push(item),
+                    None => {
+                        let _ = double.swap();
+                        thread::yield_now();
+                    }
+                }
+            }
+            producer.join().unwrap();
+
+            // Every item once and in order, across all the swaps
+            assert!(received.into_iter().eq(0..NUM_ITEMS));
+            assert!(double.buffers.iter().all(RingBuffer::is_empty));
+        }
+
+        #[test]
+        fn advance_read_commits_in_place_reads() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            for i in 0..5 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.peek_slice(), &[0, 1, 2, 3, 4]);
+            buffer.advance_read(3);
+            assert_eq!(buffer.len(), 2);
+            assert_eq!(buffer.peek_slice(), &[3, 4]);
+        }
+
+        #[test]
+        #[should_panic(expected = "advance_read")]
+        fn advance_read_past_len_panics() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            buffer.advance_read(2);
+        }
+
+        #[test]
+        fn from_raw_parts_views_share_storage() {
+            const NUM_ITEMS: u64 = 1000;
+            // Stands in for a shared-memory mapping
+            let region: *mut RingBuffer<u64, 16> = Box::into_raw(Box::new(RingBuffer::new()));
+            let addr: usize = region as usize;
+
+            let producer = thread::spawn(move || {
+                let view = unsafe { RingBuffer::<u64, 16>::from_raw_parts(addr as *const _) };
+                for i in 0..NUM_ITEMS {
+                    while !view.try_write(i) {}
+                }
+            });
+            let consumer = thread::spawn(move || {
+                let view = unsafe { RingBuffer::<u64, 16>::from_raw_parts(addr as *const _) };
+                let mut expected = 0;
+                while expected < NUM_ITEMS {
+                    if let Some(val) = view.try_read() {
+                        assert_eq!(val, expected);
+                        expected += 1;
+                    }
+                }
+            });
+
+            producer.join().unwrap();
+            consumer.join().unwrap();
+            drop(unsafe { Box::from_raw(region) });
+        }
+
+        #[test]
+        fn wrap_count_tracks_laps() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            for i in 0..10 {
+                assert!(buffer.try_write(i));
+                assert_eq!(buffer.try_read(), Some(i));
+            }
+            // 10 writes through 4 slots wrap the write cursor at writes 4 and 8
+            assert_eq!(buffer.wrap_count(), 2);
+        }
+
+        #[test]
+        fn poll_write_pending_until_read_frees_space() {
+            use std::task::Wake;
+
+            struct Flag(AtomicBool);
+            impl Wake for Flag {
+                fn wake(self: Arc<Self>) {
+                    self.0.store(true, Ordering::SeqCst);
+                }
+            }
+
+            let flag = Arc::new(Flag(AtomicBool::new(false)));
+            let waker = Waker::from(flag.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            let buffer: RingBuffer<i32, 2> = RingBuffer::with_waker();
+            let mut item = Some(1);
+            assert_eq!(buffer.poll_write(&mut cx, &mut item), Poll::Ready(()));
+            assert_eq!(item, None);
+
+            let mut item = Some(2);
+            assert_eq!(buffer.poll_write(&mut cx, &mut item), Poll::Pending);
+            assert_eq!(item, Some(2)); // Not lost on Pending
+            assert!(!flag.0.load(Ordering::SeqCst));
+
+            assert_eq!(buffer.try_read(), Some(1));
+            assert!(flag.0.load(Ordering::SeqCst));
+            assert_eq!(buffer.poll_write(&mut cx, &mut item), Poll::Ready(()));
+            assert_eq!(buffer.try_read(), Some(2));
+        }
+
+        #[test]
+        fn spsc_handles_are_not_clone() {
+            // Ambiguous (and so fails to compile) if the Spsc producer were Clone
+            trait AmbiguousIfClone<A> {
+                fn check() {}
+            }
+            impl<X> AmbiguousIfClone<()> for X {}
+            impl<X: Clone> AmbiguousIfClone<u8> for X {}
+            <Producer<i32, 4, Spsc> as AmbiguousIfClone<_>>::check();
+            <Consumer<i32, 4, Spsc> as AmbiguousIfClone<_>>::check();
+
+            fn assert_clone<X: Clone>() {}
+            assert_clone::<Producer<i32, 4, Mpmc>>();
+            assert_clone::<Consumer<i32, 4, Mpmc>>();
+        }
+
+        #[test]
+        fn mpmc_stalled_consumer_keeps_its_slot() {
+            let (producer, consumer) = RingBuffer::<i32, 4>::new().split();
+            let other: Consumer<i32, 4> = consumer.clone();
+            for i in 1..=3 {
+                assert!(producer.try_write(i));
+            }
+            // consumer claims position 0 and stalls before taking the slot
+            let ring: &RingBuffer<i32, 4> = &consumer.channel.ring;
+            ring.read_cursor.store(1, Ordering::Release);
+            assert_eq!(other.try_read(), Some(2));
+            assert_eq!(other.try_read(), Some(3));
+
+            assert!(producer.try_write(4)); // Position 3
+            assert!(!producer.try_write(5)); // Position 4 reuses slot 0, still being read
+
+            assert_eq!(unsafe { ring.take_slot(0) }, 1);
+            ring.free_slot(0);
+            assert!(producer.try_write(5));
+            assert_eq!(other.try_read(), Some(4));
+            assert_eq!(consumer.try_read(), Some(5));
+        }
+
+        #[test]
+        fn mpmc_cloned_handles_deliver_each_item_once() {
+            const NUM_PRODUCERS: u32 = 2;
+            const NUM_ITEMS: u32 = 10_000;
+            let (producer, consumer) = RingBuffer::<Vec<u32>, 4>::new().split();
+
+            let producers: Vec<thread::JoinHandle<()>> = (0..NUM_PRODUCERS)
+                .map(|id| {
+                    let producer = producer.clone();
+                    thread::spawn(move || {
+                        for i in 0..NUM_ITEMS {
+                            // Heap payloads, so a slot overwritten mid-take shows up as a
+                            // torn vec or a double free
+                            while !producer.try_write(vec![id, i]) {
+                                thread::yield_now();
+                            }
+                        }
+                    })
+                })
+                .collect();
+            drop(producer);
+
+            let consumers: Vec<thread::JoinHandle<Vec<Vec<u32>>>> = (0..2)
+                .map(|_| {
+                    let consumer = consumer.clone();
+                    thread::spawn(move || consumer.iter().collect())
+                })
+                .collect();
+            drop(consumer);
+
+            for handle in producers {
+                handle.join().unwrap();
+            }
+            let mut seen: Vec<Vec<bool>> = vec![vec![false; NUM_ITEMS as usize]; NUM_PRODUCERS as usize];
+            for handle in consumers {
+                let received: Vec<Vec<u32>> = handle.join().unwrap();
+                for id in 0..NUM_PRODUCERS {
+                    // Each consumer sees a producer's items in the order they were written
+                    let from_id: Vec<u32> = received
+                        .iter()
+                        .filter(|item| item[0] == id)
+                        .map(|item| item[1])
+                        .collect();
+                    assert!(from_id.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+                for item in received {
+                    assert_eq!(item.len(), 2, "read a slot that was not written");
+                    let slot: &mut bool = &mut seen[item[0] as usize][item[1] as usize];
+                    assert!(!*slot, "item {item:?} delivered twice");
+                    *slot = true;
+                }
+            }
+            assert!(seen.iter().flatten().all(|&delivered| delivered));
+        }
+
+        #[test]
+        fn spsc_split_preserves_order() {
+            const NUM_ITEMS: usize = 1000;
+            let (producer, consumer) = RingBuffer::<usize, 8>::new().split_spsc();
+
+            let producer = thread::spawn(move || {
+                for i in 0..NUM_ITEMS {
+                    while !producer.try_write(i) {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let received: Vec<usize> = consumer.iter().collect();
+            producer.join().unwrap();
+            assert!(received.into_iter().eq(0..NUM_ITEMS));
+        }
+
+        #[cfg(debug_assertions)]
+        #[test]
+        #[should_panic(expected = "read of unpublished slot")]
+        fn debug_read_of_unpublished_slot_panics() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            // Reproduce the old publish-order bug: the cursor moves before the slot is written
+            buffer.write_claim.store(1, Ordering::Relaxed);
+            buffer.write_cursor.store(1, Ordering::Release);
+            buffer.try_read();
+        }
+
+        #[test]
+        fn force_write_n_evicts_oldest() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert_eq!(buffer.force_write_n(0..10), 7);
+            assert_eq!(buffer.into_vec(), vec![7, 8, 9]);
+        }
+
+        #[test]
+        fn pooled_steady_state_does_not_allocate() {
+            let pool: PooledRingBuffer<[u64; 32], 4> = PooledRingBuffer::new();
+
+            let pump = |round: u64| {
+                let mut slot = pool.acquire().expect("a recycled payload");
+                slot.fill(round);
+                slot.publish();
+                let item = pool.consume().expect("the published payload");
+                assert!(item.iter().all(|&word| word == round));
+            };
+            pump(0); // Warm up
+
+            let before = allocations();
+            for round in 1..1000 {
+                pump(round);
+            }
+            assert_eq!(allocations(), before);
+
+            // Dropping an unpublished slot recycles it too
+            let slots: Vec<_> = std::iter::from_fn(|| pool.acquire()).collect();
+            assert_eq!(slots.len(), 3);
+            drop(slots);
+            assert!(pool.acquire().is_some());
+        }
+
+        #[test]
+        fn reserved_frames_never_interleave() {
+            const FRAME: usize = 3;
+            const FRAMES_PER_PRODUCER: usize = 200;
+            let buffer: Arc<RingBuffer<usize, 16>> = Arc::new(RingBuffer::new());
+
+            let producers: Vec<_> = (0..2)
+                .map(|producer| {
+                    let buffer = buffer.clone();
+                    thread::spawn(move || {
+                        for frame in 0..FRAMES_PER_PRODUCER {
+                            let tag = producer * 1000 + frame;
+                            let mut reservation = loop {
+                                match buffer.try_reserve_n(FRAME) {
+                                    Some(reservation) => break reservation,
+                                    None => thread::yield_now(),
+                                }
+                            };
+                            let (head, tail) = reservation.slices();
+                            head.fill(tag);
+                            tail.fill(tag);
+                            reservation.commit();
+                        }
+                    })
+                })
+                .collect();
+
+            let mut frames = 0;
+            let mut current = Vec::with_capacity(FRAME);
+            while frames < 2 * FRAMES_PER_PRODUCER {
+                match buffer.try_read() {
+                    Some(val) => current.push(val),
+                    None => thread::yield_now(),
+                }
+                if current.len() == FRAME {
+                    assert!(
+                        current.iter().all(|&val| val == current[0]),
+                        "interleaved frame {current:?}"
+                    );
+                    current.clear();
+                    frames += 1;
+                }
+            }
+            for producer in producers {
+                producer.join().unwrap();
+            }
+            assert!(buffer.try_reserve_n(16).is_none());
+        }
+
+        #[test]
+        fn batch_publish_makes_every_slot_visible() {
+            const FRAME: usize = 5;
+            const NUM_FRAMES: u32 = 2_000;
+            let buffer: Arc<RingBuffer<Vec<u32>, 8>> = Arc::new(RingBuffer::new());
+
+            let producer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    for frame in 0..NUM_FRAMES {
+                        let mut reservation = loop {
+                            match buffer.try_reserve_n(FRAME) {
+                                Some(reservation) => break reservation,
+                                None => thread::yield_now(),
+                            }
+                        };
+                        let (head, tail) = reservation.slices();
+                        for (offset, slot) in head.iter_mut().chain(tail).enumerate() {
+                            *slot = vec![frame, offset as u32];
+                        }
+                    } // Dropping the guard publishes the frame with one pair of fences
+                })
+            };
+
+            // Every slot of a frame is fully written by the time the consumer sees it
+            for frame in 0..NUM_FRAMES {
+                for offset in 0..FRAME as u32 {
+                    let item: Vec<u32> = loop {
+                        match buffer.try_read() {
+                            Some(item) => break item,
+                            None => thread::yield_now(),
+                        }
+                    };
+                    assert_eq!(item, vec![frame, offset]);
+                }
+            }
+            producer.join().unwrap();
+        }
+
+        #[test]
+        fn publish_fence_between_writes() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            buffer.publish_fence();
+            assert!(buffer.try_write(2));
+            assert_eq!(buffer.into_vec(), vec![1, 2]);
+        }
+
+        #[test]
+        fn capacity_const_sizes_arrays() {
+            const N: usize = RingBuffer::<i32, 8>::CAPACITY;
+            let scratch: [i32; N] = [0; N];
+            assert_eq!(scratch.len(), 7);
+            assert_eq!(RingBuffer::<i32, 8>::new().capacity(), N);
+        }
+
+        #[test]
+        fn size_two_holds_one_item() {
+            let buffer: RingBuffer<i32, 2> = RingBuffer::new();
+            assert_eq!(buffer.capacity(), 1);
+            for round in 0..4 {
+                assert!(buffer.try_write(round));
+                assert!(!buffer.try_write(round + 100)); // Full at one item
+                assert!(buffer.is_full());
+                assert_eq!(buffer.len(), 1);
+                assert!(buffer.try_reserve_n(1).is_none());
+
+                assert_eq!(buffer.try_read(), Some(round));
+                assert!(buffer.is_empty());
+                assert!(!buffer.is_full());
+            }
+
+            let (producer, consumer) = RingBuffer::<i32, 2>::new().split_spsc();
+            for round in 0..4 {
+                assert!(producer.try_write(round));
+                assert!(!producer.try_write(round + 100));
+                assert_eq!(consumer.try_read(), Some(round));
+            }
+        }
+
+        #[test]
+        fn try_read_into_array_fills_prefix() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            for i in 1..=3 {
+                assert!(buffer.try_write(i));
+            }
+
+            let mut out = [MaybeUninit::new(-1); 4];
+            assert_eq!(buffer.try_read_into_array(&mut out), 3);
+            let out = out.map(|slot| unsafe { slot.assume_init() });
+            assert_eq!(out, [1, 2, 3, -1]); // 4th slot untouched
+            assert!(buffer.is_empty());
+        }
+
+        #[test]
+        fn spsc_consumer_sees_complete_writes() {
+            const NUM_ITEMS: usize = 2000;
+            let (producer, consumer) = RingBuffer::<String, 4>::new().split_spsc();
+
+            let producer = thread::spawn(move || {
+                for i in 0..NUM_ITEMS {
+                    while !producer.try_write(i.to_string()) {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let mut expected = 0;
+            while expected < NUM_ITEMS {
+                match consumer.try_read() {
+                    Some(item) => {
+                        assert_eq!(item, expected.to_string());
+                        expected += 1;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+            producer.join().unwrap();
+            assert_eq!(consumer.try_read(), None);
+        }
+
+        #[test]
+        fn is_consumed_flips_after_read() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            let pos = buffer.try_write_at(2).expect("space for the item");
+            assert_eq!(pos, 1);
+            assert!(!buffer.is_consumed(pos));
+
+            assert_eq!(buffer.try_read(), Some(1));
+            assert!(!buffer.is_consumed(pos));
+            assert_eq!(buffer.try_read(), Some(2));
+            assert!(buffer.is_consumed(pos));
+
+            // The slot is reused a lap later, but pos stays consumed
+            for value in 0..4 {
+                assert!(buffer.try_write(value));
+                assert_eq!(buffer.try_read(), Some(value));
+            }
+            assert!(buffer.is_consumed(pos));
+        }
+
+        #[test]
+        fn try_write_at_reports_positions_across_wrap() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            let start: u32 = u32::MAX - 1;
+            for offset in 0..4 {
+                // Free for the first lap from start
+                let pos: u32 = advance_cursor(start, offset);
+                buffer.slot_seq[pos as usize & 3].store(pos, Ordering::Relaxed);
+            }
+            buffer.write_claim.store(start, Ordering::Relaxed);
+            buffer.write_cursor.store(start, Ordering::Relaxed);
+            buffer.read_cursor.store(start, Ordering::Relaxed);
+
+            let first: u32 = buffer.try_write_at(1).expect("space for the item");
+            let second: u32 = buffer.try_write_at(2).expect("space for the item");
+            let third: u32 = buffer.try_write_at(3).expect("space for the item");
+            assert_eq!((first, second, third), (u32::MAX - 1, u32::MAX, 0));
+            assert!(!buffer.is_consumed(third));
+
+            assert_eq!(buffer.try_read(), Some(1));
+            assert!(buffer.is_consumed(first));
+            assert!(!buffer.is_consumed(second));
+            assert_eq!(buffer.try_read(), Some(2));
+            assert_eq!(buffer.try_read(), Some(3));
+            assert!(buffer.is_consumed(third));
+        }
+
+        #[test]
+        fn write_or_drop_waits_within_budget() {
+            let buffer: Arc<RingBuffer<i32, 4>> = Arc::new(RingBuffer::new());
+            for i in 0..3 {
+                assert!(buffer.try_write(i));
+            }
+
+            let consumer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(20));
+                    buffer.try_read()
+                })
+            };
+            assert!(buffer.write_or_drop(3, Duration::from_millis(100)));
+            assert_eq!(consumer.join().unwrap(), Some(0));
+
+            // Nobody frees space this time
+            let start = Instant::now();
+            assert!(!buffer.write_or_drop(4, Duration::from_millis(100)));
+            assert!(start.elapsed() >= Duration::from_millis(100));
+            assert_eq!(Arc::try_unwrap(buffer).ok().unwrap().into_vec(), vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn for_each_visits_without_draining() {
+            #[derive(Default)]
+            struct Sample {
+                weight: u32,
+            }
+
+            let buffer: RingBuffer<Sample, 4> = RingBuffer::new();
+            // Move the cursors so the items wrap past the end of the storage
+            assert!(buffer.try_write(Sample { weight: 0 }));
+            assert!(buffer.try_write(Sample { weight: 0 }));
+            buffer.advance_read(2);
+            for weight in [1, 2, 4] {
+                assert!(buffer.try_write(Sample { weight }));
+            }
+
+            let mut seen = Vec::new();
+            buffer.for_each(|sample| seen.push(sample.weight));
+            assert_eq!(seen, vec![1, 2, 4]);
+            assert_eq!(buffer.len(), 3);
+            assert_eq!(buffer.try_read().map(|sample| sample.weight), Some(1));
+        }
+
+        #[test]
+        fn panic_in_emplace_poisons_consumer() {
+            let buffer: Arc<RingBuffer<String, 8>> = Arc::new(RingBuffer::new());
+            assert!(buffer.try_emplace(|slot| slot.push_str("whole")));
+
+            let panicking = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    buffer.try_emplace(|slot| {
+                        slot.push_str("half");
+                        panic!("producer died mid-write");
+                    })
+                })
+            };
+            assert!(panicking.join().is_err());
+
+            // Items before the broken frame are still delivered, then the poison
+            assert_eq!(buffer.try_read_checked(), Ok(Some("whole".to_string())));
+            assert_eq!(buffer.try_read_checked(), Err(Poisoned));
+            assert!(!buffer.try_write("late".to_string()));
+            assert_eq!(buffer.try_read_checked(), Err(Poisoned));
+        }
+
+        #[test]
+        fn assert_ring_fits_accepts_budgeted_sizes() {
+            assert_ring_fits!(u64, 512, 4096);
+            assert_ring_fits!([u8; 32], 16, 512);
+            // assert_ring_fits!([u8; 32], 32, 512) would fail to compile
+            let buffer: RingBuffer<[u8; 32], 16> = RingBuffer::new();
+            assert!(buffer.try_write([1; 32]));
+        }
+
+        #[test]
+        fn occupancy_snapshot_consistent_under_load() {
+            const NUM_ITEMS: usize = 5000;
+            let buffer: Arc<RingBuffer<usize, 8>> = Arc::new(RingBuffer::new());
+            let mask = RingBuffer::<usize, 8>::BUFFER_MASK;
+
+            let producer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    for i in 0..NUM_ITEMS {
+                        while !buffer.try_write(i) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            };
+            let consumer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    let mut received = 0;
+                    while received < NUM_ITEMS {
+                        match buffer.try_read() {
+                            Some(_) => received += 1,
+                            None => thread::yield_now(),
+                        }
+                    }
+                })
+            };
+
+            while !producer.is_finished() || !consumer.is_finished() {
+                let (head, tail, len) = buffer.occupancy_snapshot();
+                assert_eq!(len, (tail.wrapping_sub(head) & mask) as usize);
+                assert!(len <= buffer.capacity());
+            }
+            producer.join().unwrap();
+            consumer.join().unwrap();
+            assert_eq!(buffer.occupancy_snapshot().2, 0);
+        }
+
+        #[test]
+        fn try_next_reports_disconnect_after_drain() {
+            let (producer, consumer) = RingBuffer::<i32, 4>::new().split();
+            assert_eq!(consumer.try_next(), Ok(None)); // Empty but connected
+
+            assert!(producer.try_write(1));
+            assert!(producer.try_write(2));
+            assert_eq!(consumer.try_next(), Ok(Some(1)));
+            drop(producer);
+
+            // Buffered items are still delivered after the disconnect
+            assert_eq!(consumer.try_next(), Ok(Some(2)));
+            assert_eq!(consumer.try_next(), Err(Disconnected));
+        }
+
+        #[test]
+        fn occupancy_histogram_buckets_by_quarter() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.try_write(0));
+            assert_eq!(buffer.try_read(), Some(0));
+            assert_eq!(buffer.occupancy_histogram(), [0; 4]); // Off by default
+
+            buffer.set_profiling(true);
+            // Near-empty traffic: every write leaves one item buffered
+            for i in 0..10 {
+                assert!(buffer.try_write(i));
+                assert_eq!(buffer.try_read(), Some(i));
+            }
+            assert_eq!(buffer.occupancy_histogram(), [10, 0, 0, 0]);
+
+            // Filling up leaves 1..=7 of 8 slots in use
+            for i in 0..7 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.occupancy_histogram(), [11, 2, 2, 2]);
+
+            buffer.set_profiling(false);
+            assert_eq!(buffer.try_read(), Some(0));
+            assert!(buffer.try_write(7));
+            assert_eq!(buffer.occupancy_histogram(), [11, 2, 2, 2]);
+        }
+
+        #[test]
+        #[should_panic(expected = "with_waker")]
+        fn poll_write_needs_with_waker() {
+            let waker: Waker = Waker::noop().clone();
+            let mut cx = Context::from_waker(&waker);
+            let buffer: RingBuffer<i32, 2> = RingBuffer::new();
+            let _ = buffer.poll_write(&mut cx, &mut Some(1));
+        }
+
+        #[test]
+        fn reunite_returns_unread_items() {
+            let (producer, consumer) = RingBuffer::<i32, 8>::new().split();
+            for i in 1..=3 {
+                assert!(producer.try_write(i));
+            }
+            assert_eq!(consumer.try_read(), Some(1));
+
+            let buffer = RingBuffer::reunite(producer, consumer)
+                .ok()
+                .expect("halves of one split");
+            assert_eq!(buffer.into_vec(), vec![2, 3]);
+
+            // Halves of different buffers are handed back
+            let (producer_a, consumer_a) = RingBuffer::<i32, 8>::new().split();
+            let (producer_b, consumer_b) = RingBuffer::<i32, 8>::new().split();
+            let (producer_a, consumer_b) = RingBuffer::reunite(producer_a, consumer_b).expect_err("mismatched");
+
+            // So are halves with another live producer
+            let extra = producer_a.clone();
+            let (producer_a, consumer_a) = RingBuffer::reunite(producer_a, consumer_a).expect_err("clone alive");
+            drop(extra);
+            assert!(RingBuffer::reunite(producer_a, consumer_a).is_ok());
+            assert!(RingBuffer::reunite(producer_b, consumer_b).is_ok());
+        }
+
+        #[test]
+        fn spsc_stale_read_cache_never_overwrites() {
+            let (mut producer, consumer) = RingBuffer::<i32, 4>::new().split_spsc();
+            producer.set_read_refresh_interval(u32::MAX);
+            assert!(producer.try_write(1));
+            assert!(producer.try_write(2));
+            assert!(producer.try_write(3));
+            assert!(!producer.try_write(4)); // Full on the real cursor too
+
+            assert_eq!(consumer.try_read(), Some(1));
+            assert!(producer.try_write(4)); // Apparent full forces a reload
+            assert!(!producer.try_write(5));
+            assert_eq!(consumer.try_read(), Some(2));
+            assert_eq!(consumer.try_read(), Some(3));
+            assert_eq!(consumer.try_read(), Some(4));
+            assert_eq!(consumer.try_read(), None);
+
+            const NUM_ITEMS: usize = 2000;
+            let (mut producer, consumer) = RingBuffer::<String, 8>::new().split_spsc();
+            producer.set_read_refresh_interval(64);
+            let producer = thread::spawn(move || {
+                for i in 0..NUM_ITEMS {
+                    while !producer.try_write(i.to_string()) {
+                        thread::yield_now();
+                    }
+                }
+            });
+
+            let mut expected = 0;
+            while expected < NUM_ITEMS {
+                match consumer.try_read() {
+                    Some(item) => {
+                        assert_eq!(item, expected.to_string());
+                        expected += 1;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+            producer.join().unwrap();
+            assert_eq!(consumer.try_read(), None);
+        }
+
+        #[test]
+        fn local_ring_buffer_round_trips_rc() {
+            use std::rc::Rc;
+
+            let buffer: LocalRingBuffer<Option<Rc<i32>>, 4> = LocalRingBuffer::new();
+            let shared: Rc<i32> = Rc::new(7);
+            for _ in 0..3 {
+                assert!(buffer.try_write(Some(shared.clone())));
+            }
+            assert!(!buffer.try_write(Some(shared.clone()))); // Buffer is full
+            assert_eq!(buffer.len(), 3);
+            assert_eq!(Rc::strong_count(&shared), 4);
+
+            while let Some(item) = buffer.try_read() {
+                assert_eq!(item.as_deref(), Some(&7));
+            }
+            assert!(buffer.is_empty());
+            assert_eq!(Rc::strong_count(&shared), 1); // Nothing left behind in the slots
+        }
+
+        #[test]
+        fn peek_at_indexes_from_oldest() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(10));
+            assert!(buffer.try_write(20));
+            assert!(buffer.try_write(30));
+            assert_eq!(buffer.peek_at(0), Some(10));
+            assert_eq!(buffer.peek_at(2), Some(30));
+            assert_eq!(buffer.peek_at(3), None);
+
+            // Wrap the write cursor past the end of the storage
+            assert_eq!(buffer.try_read(), Some(10));
+            assert_eq!(buffer.try_read(), Some(20));
+            assert!(buffer.try_write(40));
+            assert!(buffer.try_write(50));
+            assert_eq!(buffer.peek_at(1), Some(40));
+            assert_eq!(buffer.peek_at(2), Some(50));
+            assert_eq!(buffer.len(), 3); // Nothing consumed
+        }
+
+        #[test]
+        fn write_slice_blocking_transfers_more_than_capacity() {
+            let buffer: Arc<RingBuffer<u32, 8>> = Arc::new(RingBuffer::new());
+            let src: Vec<u32> = (0..100).collect();
+
+            let consumer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    let mut received: Vec<u32> = Vec::new();
+                    while received.len() < 100 {
+                        match buffer.try_read() {
+                            Some(item) => received.push(item),
+                            None => thread::yield_now(),
+                        }
+                    }
+                    received
+                })
+            };
+            buffer.write_slice_blocking(&src);
+            assert_eq!(consumer.join().unwrap(), src);
+            assert!(buffer.is_empty());
+        }
+
+        #[test]
+        fn is_lock_free_on_common_targets() {
+            assert!(RingBuffer::<i32, 4>::is_lock_free());
+        }
+
+        #[test]
+        fn drain_batch_with_stops_on_break() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            for i in 0..3 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.try_read(), Some(0));
+            assert_eq!(buffer.try_read(), Some(1));
+            for i in 3..5 {
+                assert!(buffer.try_write(i)); // Wraps: chunks [2, 3] and [4]
+            }
+
+            let mut chunks: Vec<Vec<i32>> = Vec::new();
+            let accepted = buffer.drain_batch_with(|chunk| {
+                chunks.push(chunk.to_vec());
+                if chunks.len() == 1 {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(()) // Push back on the second chunk
+                }
+            });
+            assert_eq!(chunks, vec![vec![2, 3], vec![4]]);
+            assert_eq!(accepted, 2);
+            assert_eq!(buffer.read_position(), 4); // Advanced by exactly the first chunk
+            assert_eq!(buffer.try_read(), Some(4));
+            assert_eq!(buffer.drain_batch_with(|_| ControlFlow::Continue(())), 0);
+        }
+
+        #[test]
+        fn used_slots_matches_cursor_comparison() {
+            type Rb = RingBuffer<i32, 8>;
+            for read in 0..8u32 {
+                for write in 0..8u32 {
+                    let next_write = (write + 1) & Rb::BUFFER_MASK;
+                    assert_eq!(Rb::used_slots(write, read) == Rb::BUFFER_MASK, next_write == read);
+                    assert_eq!(Rb::used_slots(write, read) == 0, write == read);
+                }
+            }
+        }
+
+        #[test]
+        fn named_buffer_shows_label_in_debug() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::named("audio-in");
+            assert!(buffer.try_write(1));
+            assert_eq!(buffer.label(), Some("audio-in"));
+
+            let debug: String = format!("{buffer:?}");
+            assert!(debug.contains("\"audio-in\""), "{debug}");
+            assert!(debug.contains("len: 1"), "{debug}");
+            assert!(format!("{:?}", RingBuffer::<i32, 4>::new()).contains("label: None"));
+        }
+
+        #[test]
+        fn advance_cursor_wraps_at_u32_max() {
+            assert_eq!(advance_cursor(u32::MAX, 1), 0);
+            assert_eq!(advance_cursor(u32::MAX - 1, 3), 1);
+            assert_eq!(advance_cursor(6, 1), 7);
+
+            // Drive the free-running cursors across u32::MAX
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            let start: u32 = u32::MAX - 2;
+            for offset in 0..8 {
+                // Free for the first lap from start
+                let pos: u32 = advance_cursor(start, offset);
+                buffer.slot_seq[pos as usize & 7].store(pos, Ordering::Relaxed);
+            }
+            buffer.write_claim.store(start, Ordering::Relaxed);
+            buffer.write_cursor.store(start, Ordering::Relaxed);
+            buffer.read_cursor.store(start, Ordering::Relaxed);
+            for round in 0..3 {
+                for i in 0..5 {
+                    assert!(buffer.try_write(round * 10 + i));
+                }
+                assert_eq!(buffer.len(), 5);
+                for i in 0..5 {
+                    assert_eq!(buffer.try_read(), Some(round * 10 + i));
+                }
+            }
+            assert_eq!(buffer.read_cursor.load(Ordering::Relaxed), start.wrapping_add(15));
+            assert_eq!(buffer.read_position(), start.wrapping_add(15));
+            assert_eq!(buffer.read_position() & 7, 4);
+        }
+
+        #[test]
+        fn read_coalesced_folds_buffered_items() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert_eq!(buffer.read_coalesced(4, |a, b| a + b), None);
+            for i in 1..=4 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.read_coalesced(4, |a, b| a + b), Some(10));
+            assert!(buffer.is_empty());
+
+            for i in 1..=4 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.read_coalesced(3, |a, b| a + b), Some(6)); // Stops at max
+            assert_eq!(buffer.try_read(), Some(4));
+        }
+
+        #[test]
+        fn try_write_spins_is_bounded() {
+            let buffer: Arc<RingBuffer<i32, 4>> = Arc::new(RingBuffer::new());
+            for i in 0..3 {
+                assert!(buffer.try_write(i));
+            }
+            assert_eq!(buffer.try_write_spins(3, 0), Err(3));
+            assert_eq!(buffer.try_write_spins(3, 1000), Err(3)); // Nobody frees a slot
+
+            let consumer = {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        spin_loop();
+                    }
+                    buffer.try_read()
+                })
+            };
+            let mut item = 3;
+            while let Err(rejected) = buffer.try_write_spins(item, 1000) {
+                item = rejected;
+                thread::yield_now();
+            }
+            assert_eq!(consumer.join().unwrap(), Some(0));
+            assert_eq!(buffer.try_write_spins(4, 0), Err(4));
+            assert_eq!(Arc::try_unwrap(buffer).ok().unwrap().into_vec(), vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn round_capacity_rounds_up_to_power_of_two() {
+            assert_eq!(round_capacity(100), 128);
+            assert_eq!(round_capacity(128), 128);
+            assert_eq!(round_capacity(0), 2);
+            assert_eq!(round_capacity(1), 2);
+            assert_eq!(round_capacity(3), 4);
+            assert_eq!(round_capacity(usize::MAX / 2 + 1), usize::MAX / 2 + 1);
+            assert_eq!(RingBuffer::<u8, { round_capacity(100) }>::CAPACITY, 127);
+        }
+
+        #[test]
+        #[should_panic(expected = "overflows usize")]
+        fn round_capacity_panics_on_overflow() {
+            round_capacity(usize::MAX / 2 + 2);
+        }
+
+        #[test]
+        fn drain_to_level_leaves_reserve() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            for i in 0..6 {
+                assert!(buffer.try_write(i));
+            }
+            let mut out: Vec<i32> = Vec::new();
+            assert_eq!(buffer.drain_to_level(2, &mut out), 4);
+            assert_eq!(out, vec![0, 1, 2, 3]);
+            assert_eq!(buffer.len(), 2);
+
+            assert_eq!(buffer.drain_to_level(2, &mut out), 0); // Already at the level
+            assert_eq!(buffer.drain_to_level(5, &mut out), 0);
+            assert_eq!(out.len(), 4);
+        }
+
+        #[test]
+        fn read_unchecked_reads_published_item() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(7));
+            assert!(buffer.try_write(8));
+            // SAFETY: two items were written on this thread and nobody else reads
+            assert_eq!(unsafe { buffer.read_unchecked() }, 7);
+            assert_eq!(unsafe { buffer.read_unchecked() }, 8);
+            assert!(buffer.is_empty());
+        }
+
+        #[test]
+        fn readable_io_slices_cover_wrapped_data() {
+            use std::io::Write;
+
+            let buffer: RingBuffer<u8, 8> = RingBuffer::new();
+            for &byte in b"xxxxx" {
+                assert!(buffer.try_write(byte));
+            }
+            buffer.advance_read(5);
+            for &byte in b"hello" {
+                assert!(buffer.try_write(byte)); // Wraps after "hel"
+            }
+
+            let slices = buffer.readable_io_slices();
+            assert_eq!(&*slices[0], b"hel");
+            assert_eq!(&*slices[1], b"lo");
+
+            let mut out: Vec<u8> = Vec::new();
+            let written: usize = out.write_vectored(&slices).unwrap();
+            assert_eq!(out, b"hello");
+            buffer.advance_read(written);
+            assert!(buffer.is_empty());
+            assert_eq!(&*buffer.readable_io_slices()[0], b"");
+        }
+
+        #[test]
+        fn from_array_seeds_a_static() {
+            static SEEDED: RingBuffer<u8, 8> = RingBuffer::from_array([1, 2, 3]);
+            assert_eq!(SEEDED.len(), 3);
+            assert_eq!(SEEDED.try_read(), Some(1));
+            assert_eq!(SEEDED.try_read(), Some(2));
+            assert_eq!(SEEDED.try_read(), Some(3));
+            assert_eq!(SEEDED.try_read(), None);
+
+            assert!(SEEDED.try_write(4)); // Behaves like any other buffer afterwards
+            assert_eq!(SEEDED.try_read(), Some(4));
+        }
+
+        #[test]
+        fn from_array_accepts_one_to_capacity_items() {
+            // N == 1, the smallest seed: the tail is filled from the only item
+            let single: RingBuffer<u8, 4> = RingBuffer::from_array([7]);
+            assert_eq!(single.len(), 1);
+            assert_eq!(single.try_read(), Some(7));
+            assert_eq!(single.try_read(), None);
+
+            // N == SIZE - 1 seeds a full buffer
+            let full: RingBuffer<u8, 4> = RingBuffer::from_array([1, 2, 3]);
+            assert!(full.is_full());
+            assert!(!full.try_write(4));
+            assert_eq!(full.into_vec(), vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn write_behind_a_stalled_claim_does_not_wait() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            // A producer that claimed slot 0 and was preempted before writing it
+            buffer.write_claim.store(1, Ordering::Relaxed);
+
+            assert!(buffer.try_write(2)); // Returns at once instead of spinning
+            assert_eq!(buffer.try_read(), None); // Consumers still see claim order
+
+            // The stalled producer finishes and moves the cursor over both slots
+            unsafe { buffer.write_slot(0, 1) };
+            buffer.publish(0, 1);
+            assert_eq!(buffer.len(), 2);
+            assert_eq!(buffer.try_read(), Some(1));
+            assert_eq!(buffer.try_read(), Some(2));
+        }
+
+        #[test]
+        fn outstanding_reservation_does_not_block_writes() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            let mut reservation = buffer.try_reserve_n(1).expect("space for the frame");
+            assert!(buffer.try_write(5)); // Same thread, guard still alive
+            assert_eq!(buffer.try_read(), None);
+            reservation.slices().0[0] = 4;
+            drop(reservation);
+            assert_eq!(buffer.try_read(), Some(4));
+            assert_eq!(buffer.try_read(), Some(5));
+
+            // A closure that writes to the buffer it is emplacing into
+            assert!(buffer.try_emplace(|slot| {
+                *slot = 6;
+                assert!(buffer.try_write(7));
+            }));
+            assert_eq!(buffer.try_read(), Some(6));
+            assert_eq!(buffer.try_read(), Some(7));
+
+            // A leaked guard stalls consumers, but writes still return
+            std::mem::forget(buffer.try_reserve_n(1));
+            for i in 0..6 {
+                assert!(buffer.try_write(i));
+            }
+            assert!(!buffer.try_write(6)); // Full
+            assert_eq!(buffer.try_read(), None);
+        }
+
+        // Poisons the buffer through a try_emplace closure that panics on this thread
+        fn poison_buffer<const SIZE: usize>(buffer: &RingBuffer<i32, SIZE>) {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                buffer.try_emplace(|_| panic!("producer died mid-write"))
+            }));
+            assert!(result.is_err());
+            assert!(buffer.is_poisoned());
+        }
+
+        #[test]
+        fn blocking_read_tells_poison_from_disconnect() {
+            let (producer, consumer) = RingBuffer::<i32, 4>::new().split();
+            assert!(producer.try_write(1));
+            poison_buffer(&producer.channel.ring);
+            // The producer is still alive, so only the poison ends the wait
+            assert_eq!(consumer.read(), Ok(Some(1)));
+            assert_eq!(consumer.read(), Err(Poisoned));
+            drop(producer);
+            assert_eq!(consumer.read(), Err(Poisoned));
+            assert_eq!(consumer.iter().next(), None);
+
+            let (producer, consumer) = RingBuffer::<i32, 4>::new().split();
+            let writer = thread::spawn(move || {
+                for i in 0..3 {
+                    while !producer.try_write(i) {
+                        thread::yield_now();
+                    }
+                }
+            });
+            for i in 0..3 {
+                assert_eq!(consumer.read(), Ok(Some(i)));
+            }
+            writer.join().unwrap();
+            assert_eq!(consumer.read(), Ok(None));
+        }
+
+        #[test]
+        fn force_write_n_stops_on_poisoned_buffer() {
+            let buffer: RingBuffer<i32, 4> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            poison_buffer(&buffer);
+            assert_eq!(buffer.force_write_n(0..10), 0); // Returns instead of spinning
+            assert_eq!(buffer.try_read_checked(), Ok(Some(1)));
+            assert_eq!(buffer.try_read_checked(), Err(Poisoned));
+        }
+
+        #[test]
+        fn write_behind_poisoned_frame_is_handed_back() {
+            let buffer: RingBuffer<i32, 8> = RingBuffer::new();
+            assert!(buffer.try_write(1));
+            let mut reservation = buffer.try_reserve_n(1).expect("space for the frame");
+            reservation.slices().0[0] = 2;
+            // A write claimed behind the frame, whose poisoning it sees only after
+            // publishing its own slot
+            buffer.write_claim.store(3, Ordering::Relaxed);
+            unsafe { buffer.write_slot(2, 3) };
+            buffer.publish(2, 1);
+            assert!(!buffer.is_behind_poisoned_frame(2));
+            buffer.poison(1);
+            std::mem::forget(reservation);
+
+            assert!(buffer.is_behind_poisoned_frame(2));
+            assert!(!buffer.is_behind_poisoned_frame(0)); // Published before the frame
+            assert_eq!(buffer.try_write_at(4), None);
+            assert_eq!(buffer.try_read_checked(), Ok(Some(1)));
+            assert_eq!(buffer.try_read_checked(), Err(Poisoned));
+        }
+
+        #[test]
+        fn poll_write_ready_on_poisoned_buffer() {
+            use std::task::Wake;
+
+            struct Flag(AtomicBool);
+            impl Wake for Flag {
+                fn wake(self: Arc<Self>) {
+                    self.0.store(true, Ordering::SeqCst);
+                }
+            }
+
+            let flag = Arc::new(Flag(AtomicBool::new(false)));
+            let waker = Waker::from(flag.clone());
+            let mut cx = Context::from_waker(&waker);
+
+            let buffer: RingBuffer<i32, 4> = RingBuffer::with_waker();
+            for i in 0..2 {
+                assert!(buffer.try_write(i));
+            }
+            let mut reservation = buffer.try_reserve_n(1).expect("space for the frame");
+            reservation.slices().0[0] = 2;
+            let mut item = Some(3);
+            assert_eq!(buffer.poll_write(&mut cx, &mut item), Poll::Pending);
+
+            // Poisoning wakes the parked writer, whose next poll gives up
+            buffer.poison(2);
+            std::mem::forget(reservation);
+            assert!(flag.0.load(Ordering::SeqCst));
+            assert_eq!(buffer.poll_write(&mut cx, &mut item), Poll::Ready(()));
+            assert_eq!(item, Some(3)); // Never written
         }
     }
 }